@@ -0,0 +1,53 @@
+use sdl2::Sdl;
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+
+const SAMPLE_RATE: i32 = 22050;
+const VOLUME: i16 = 1500;
+
+// Sound effects synthesized on the fly, there are no sound files yet. The game runs silently
+// when there is no audio device.
+pub struct Audio {
+    queue: Option<AudioQueue<i16>>,
+}
+
+impl Audio {
+    pub fn new(sdl_context: &Sdl) -> Audio {
+        let desired = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples: None,
+        };
+        let queue = sdl_context
+            .audio()
+            .and_then(|audio_subsystem| audio_subsystem.open_queue::<i16, _>(None, &desired))
+            .ok();
+        if let Some(ref queue) = queue {
+            queue.resume();
+        }
+        Audio { queue }
+    }
+
+    // The short blip of text appearing in a message box
+    pub fn play_text_tick(&mut self) {
+        self.play_square_wave(1200, 12);
+    }
+
+    // Moving the cursor in a menu or a Yes/No prompt
+    pub fn play_cursor(&mut self) {
+        self.play_square_wave(800, 30);
+    }
+
+    fn play_square_wave(&mut self, frequency: i32, length_ms: i32) {
+        if let Some(ref queue) = self.queue {
+            // Don't let ticks pile up behind each other when text is fast forwarded
+            queue.clear();
+            let freq = queue.spec().freq;
+            let num_samples = freq * length_ms / 1000;
+            let half_period = (freq / frequency / 2).max(1);
+            let samples: Vec<i16> = (0..num_samples)
+                .map(|i| if (i / half_period) % 2 == 0 { VOLUME } else { -VOLUME })
+                .collect();
+            queue.queue(&samples);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Converts between the original game's obfuscated .tsc scripts and plain text, and checks
+// scripts for mistakes.
+//
+//   tsc decode Cave.tsc Cave.txt
+//   tsc encode Cave.txt Cave.tsc
+//   tsc lint Cave.txt
+//   tsc lint Cave.txt Head.tsc
+//
+// Linting also accepts jumps into shared scripts. They're given after the script, or else the
+// Head.tsc (or Head.txt) next to it is used like the game does.
+
+extern crate cave_story;
+
+use cave_story::tsc::{codec, lint, Script};
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "Usage: tsc decode <input.tsc> <output.txt>
+       tsc encode <input.txt> <output.tsc>
+       tsc lint <script.tsc|script.txt> [<shared script>...]";
+
+const HEAD_SCRIPTS: &[&str] = &["Head.tsc", "Head.txt"];
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match (args.first().map(|arg| arg.as_str()), args.len()) {
+        (Some("decode"), 3) => {
+            read(&args[1]).and_then(|data| write(&args[2], &codec::decode(&data)))
+        }
+        (Some("encode"), 3) => {
+            read(&args[1]).and_then(|data| write(&args[2], &codec::encode(&data)))
+        }
+        (Some("lint"), 2) => lint_file(&args[1], &head_script(&args[1])),
+        (Some("lint"), _) if args.len() > 2 => lint_file(&args[1], &args[2..]),
+        _ => Err(String::from(USAGE)),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    let mut data = vec![];
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut data))
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(data)
+}
+
+fn write(path: &str, data: &[u8]) -> Result<(), String> {
+    File::create(path)
+        .and_then(|mut file| file.write_all(data))
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+// Decodes .tsc files, anything else is read as plain text
+fn read_script(path: &str) -> Result<String, String> {
+    let data = read(path)?;
+    let data = if path.ends_with(".tsc") {
+        codec::decode(&data)
+    } else {
+        data
+    };
+    // The original scripts are Shift-JIS, anything outside of ASCII only shows up in message text
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+// The shared script next to `path`, unless that's the one being linted
+fn head_script(path: &str) -> Vec<String> {
+    let path = Path::new(path);
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    HEAD_SCRIPTS
+        .iter()
+        .map(|name| directory.join(name))
+        .find(|head| head.is_file() && head.file_name() != path.file_name())
+        .map(|head| head.to_string_lossy().into_owned())
+        .into_iter()
+        .collect()
+}
+
+fn lint_file(path: &str, shared_paths: &[String]) -> Result<(), String> {
+    let text = read_script(path)?;
+    let mut shared = vec![];
+    for shared_path in shared_paths.iter() {
+        let script = Script::parse(&read_script(shared_path)?)
+            .map_err(|e| format!("{}: {}", shared_path, e))?;
+        shared.push(script);
+    }
+
+    let messages = lint::lint(&text, &shared);
+    for message in messages.iter() {
+        println!("{}:{}", path, message);
+    }
+    if messages.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} problem(s) found", messages.len()))
+    }
+}
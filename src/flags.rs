@@ -0,0 +1,177 @@
+use graphics::Graphics;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use std::collections::{HashMap, HashSet};
+
+// Same count as the original game, which saves them as 1000 bytes
+pub const NUM_FLAGS: usize = 8000;
+// Per map, for one-time events such as a boss intro that shouldn't replay after dying
+pub const NUM_SKIP_FLAGS: usize = 64;
+
+// Debug overlay layout: a grid of every flag, one small cell each, with the changed flags listed
+// underneath
+const OVERLAY_COLS: usize = 100;
+const OVERLAY_CELL: u32 = 5;
+const OVERLAY_X: i32 = 70;
+const OVERLAY_Y: i32 = 8;
+const OVERLAY_MARGIN: i32 = 8;
+
+// Story progress. Flags are numbered like the original game's so its scripts work unchanged;
+// numbers past the end are ignored when set and read as clear.
+pub struct Flags {
+    flags: Vec<u8>,
+    skip_flags: HashMap<String, Vec<u8>>,
+    // Flags set or cleared since the game started, oldest first, for the debug overlay
+    changed: Vec<u16>,
+    // The same flags, to look them up quickly
+    changed_set: HashSet<u16>,
+}
+
+impl Flags {
+    pub fn new() -> Flags {
+        Flags {
+            flags: vec![0; NUM_FLAGS / 8],
+            skip_flags: HashMap::new(),
+            changed: vec![],
+            changed_set: HashSet::new(),
+        }
+    }
+
+    pub fn set(&mut self, flag: u16) {
+        self.write(flag, true);
+    }
+
+    pub fn clear(&mut self, flag: u16) {
+        self.write(flag, false);
+    }
+
+    pub fn test(&self, flag: u16) -> bool {
+        test_bit(&self.flags, flag as usize)
+    }
+
+    pub fn set_skip(&mut self, map_name: &str, flag: u16) {
+        if (flag as usize) < NUM_SKIP_FLAGS {
+            let bits = self.skip_flags
+                .entry(String::from(map_name))
+                .or_insert_with(|| vec![0; NUM_SKIP_FLAGS / 8]);
+            write_bit(bits, flag as usize, true);
+        }
+    }
+
+    pub fn clear_skip(&mut self, map_name: &str, flag: u16) {
+        if let Some(bits) = self.skip_flags.get_mut(map_name) {
+            write_bit(bits, flag as usize, false);
+        }
+    }
+
+    pub fn test_skip(&self, map_name: &str, flag: u16) -> bool {
+        self.skip_flags
+            .get(map_name)
+            .map_or(false, |bits| test_bit(bits, flag as usize))
+    }
+
+    pub fn changed(&self) -> &[u16] {
+        &self.changed
+    }
+
+    // The raw bits, flag 0 in the lowest bit of the first byte, as the original saves them
+    pub fn bytes(&self) -> &[u8] {
+        &self.flags
+    }
+
+    // Flags that end up different count as changed, like with `set` and `clear`
+    pub fn set_bytes(&mut self, bytes: &[u8]) {
+        for flag in 0..NUM_FLAGS {
+            self.write(flag as u16, test_bit(bytes, flag));
+        }
+    }
+
+    // Maps with any skip flag set, and their bits
+    pub fn skip_bytes(&self) -> Vec<(&str, &[u8])> {
+        let mut maps: Vec<(&str, &[u8])> = self.skip_flags
+            .iter()
+            .filter(|&(_, bits)| bits.iter().any(|&byte| byte != 0))
+            .map(|(name, bits)| (name.as_str(), bits.as_slice()))
+            .collect();
+        maps.sort();
+        maps
+    }
+
+    pub fn set_skip_bytes(&mut self, map_name: &str, bytes: &[u8]) {
+        let mut bits = vec![0; NUM_SKIP_FLAGS / 8];
+        let len = bytes.len().min(bits.len());
+        bits[..len].copy_from_slice(&bytes[..len]);
+        self.skip_flags.insert(String::from(map_name), bits);
+    }
+
+    // Set flags in white, and ones changed this session in yellow (set) or red (cleared)
+    pub fn draw_overlay(&self, graphics: &mut Graphics) {
+        let (screen_width, screen_height) = graphics.screen_size();
+        graphics.fill_rect(Rect::new(0, 0, screen_width, screen_height),
+                           Color::RGBA(0, 0, 0, 160));
+        // Cells are grouped by color so each group is one draw call
+        let mut cells: [Vec<Rect>; 4] = [vec![], vec![], vec![], vec![]];
+        for flag in 0..NUM_FLAGS {
+            let changed = self.changed_set.contains(&(flag as u16));
+            let group = match (self.test(flag as u16), changed) {
+                (true, true) => 0,
+                (false, true) => 1,
+                (true, false) => 2,
+                (false, false) => 3,
+            };
+            let x = OVERLAY_X + ((flag % OVERLAY_COLS) as u32 * OVERLAY_CELL) as i32;
+            let y = OVERLAY_Y + ((flag / OVERLAY_COLS) as u32 * OVERLAY_CELL) as i32;
+            cells[group].push(Rect::new(x, y, OVERLAY_CELL - 1, OVERLAY_CELL - 1));
+        }
+        let colors = [Color::RGB(255, 220, 0),
+                      Color::RGB(220, 0, 0),
+                      Color::RGB(255, 255, 255),
+                      Color::RGBA(80, 80, 80, 160)];
+        for (rects, &color) in cells.iter().zip(colors.iter()) {
+            graphics.fill_rects(rects, color);
+        }
+
+        let changed: Vec<String> = self.changed
+            .iter()
+            .map(|&flag| format!("{}{}", flag, if self.test(flag) { "+" } else { "-" }))
+            .collect();
+        let text = format!("Flags changed this session: {}",
+                           if changed.is_empty() {
+                               String::from("none")
+                           } else {
+                               changed.join(" ")
+                           });
+        let text_y = OVERLAY_Y + (NUM_FLAGS / OVERLAY_COLS) as i32 * OVERLAY_CELL as i32 +
+                     OVERLAY_MARGIN;
+        let text_area = Rect::new(OVERLAY_MARGIN,
+                                  text_y,
+                                  screen_width - 2 * OVERLAY_MARGIN as u32,
+                                  screen_height - text_y as u32);
+        graphics.draw_text_wrapped(&text, text_area, Color::RGB(255, 255, 255));
+    }
+
+    fn write(&mut self, flag: u16, value: bool) {
+        if (flag as usize) >= NUM_FLAGS || self.test(flag) == value {
+            return;
+        }
+        write_bit(&mut self.flags, flag as usize, value);
+        if self.changed_set.insert(flag) {
+            self.changed.push(flag);
+        }
+    }
+}
+
+fn test_bit(bits: &[u8], index: usize) -> bool {
+    bits.get(index / 8)
+        .map_or(false, |&byte| byte & (1 << (index % 8)) != 0)
+}
+
+fn write_bit(bits: &mut [u8], index: usize, value: bool) {
+    if let Some(byte) = bits.get_mut(index / 8) {
+        if value {
+            *byte |= 1 << (index % 8);
+        } else {
+            *byte &= !(1 << (index % 8));
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::surface::Surface;
+
+pub const FONT_PATH: &str = "content/Font.bmp";
+
+// The sheet is a grid of printable ASCII glyphs starting at the space, drawn in white on black
+const FIRST_CHAR: u32 = 32;
+const NUM_GLYPHS: u32 = 95;
+const GLYPH_COLS: u32 = 16;
+const CELL_SIZE: u32 = 16;
+const LINE_HEIGHT: u32 = 18;
+const LETTER_SPACING: u32 = 2;
+const SPACE_WIDTH: u32 = 8;
+// Drawn for characters the sheet doesn't have
+const FALLBACK_CHAR: char = '?';
+
+#[derive(Clone, Copy)]
+struct Glyph {
+    // Lit columns of the glyph's cell
+    left: u32,
+    width: u32,
+}
+
+// Glyph metrics of a bitmap font. Widths are measured from the sheet's pixels, so glyphs can be
+// redrawn without touching any code.
+pub struct Font {
+    // None when the sheet couldn't be loaded, see `without_sheet`
+    sheet_path: Option<String>,
+    glyphs: Vec<Glyph>,
+}
+
+impl Font {
+    pub fn load(sheet_path: &str) -> Result<Font, String> {
+        // Measured in ARGB8888 whatever format the file was saved in
+        let format = Surface::new(1, 1, PixelFormatEnum::ARGB8888)?.pixel_format();
+        let sheet = Surface::load_bmp(sheet_path)?.convert(&format)?;
+        let glyphs = (0..NUM_GLYPHS)
+            .map(|index| measure_glyph(&sheet, index))
+            .collect::<Vec<Glyph>>();
+        Ok(Font {
+               sheet_path: Some(String::from(sheet_path)),
+               glyphs,
+           })
+    }
+
+    // Fixed width metrics for when the sheet is missing, so text still lays out the same way
+    pub fn without_sheet() -> Font {
+        Font {
+            sheet_path: None,
+            glyphs: vec![Glyph {
+                             left: 0,
+                             width: SPACE_WIDTH,
+                         };
+                         NUM_GLYPHS as usize],
+        }
+    }
+
+    pub fn sheet_path(&self) -> Option<&str> {
+        self.sheet_path.as_ref().map(|path| path.as_str())
+    }
+
+    pub fn line_height(&self) -> u32 {
+        LINE_HEIGHT
+    }
+
+    // Where the glyph for `ch` is on the sheet, and how far to move along after drawing it
+    pub fn glyph(&self, ch: char) -> (Rect, u32) {
+        let index = self.glyph_index(ch);
+        let glyph = self.glyphs[index as usize];
+        let source_rect = Rect::new(((index % GLYPH_COLS) * CELL_SIZE + glyph.left) as i32,
+                                    ((index / GLYPH_COLS) * CELL_SIZE) as i32,
+                                    glyph.width.max(1),
+                                    CELL_SIZE);
+        (source_rect, glyph.width + LETTER_SPACING)
+    }
+
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars().map(|ch| self.glyph(ch).1).sum()
+    }
+
+    // Splits text into lines no wider than `max_width`, breaking between words where possible.
+    // Newlines in the text always start a new line.
+    pub fn wrap(&self, text: &str, max_width: u32) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in text.split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    String::from(word)
+                } else {
+                    format!("{} {}", line, word)
+                };
+                if self.text_width(&candidate) <= max_width || line.is_empty() {
+                    line = candidate;
+                } else {
+                    lines.push(line);
+                    line = String::from(word);
+                }
+                // A single word wider than the box is split wherever it runs out of room
+                while self.text_width(&line) > max_width && line.chars().count() > 1 {
+                    let mut width = 0;
+                    let split = line.char_indices()
+                        .find(|&(position, ch)| {
+                                  width += self.glyph(ch).1;
+                                  width > max_width && position > 0
+                              })
+                        .map_or(line.len(), |(position, _)| position);
+                    let rest = line.split_off(split);
+                    lines.push(line);
+                    line = rest;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    // Like `wrap`, but with the character offset into `text` each line starts at. Wrapping drops
+    // the spaces and newlines lines are broken at, so counting line lengths isn't enough.
+    pub fn wrap_with_offsets(&self, text: &str, max_width: u32) -> Vec<(usize, String)> {
+        let mut byte_cursor = 0;
+        let mut char_cursor = 0;
+        self.wrap(text, max_width)
+            .into_iter()
+            .map(|line| {
+                     // Every line is a run of the text, so it's found past whatever was dropped
+                     let skipped = text[byte_cursor..].find(line.as_str()).unwrap_or(0);
+                     char_cursor += text[byte_cursor..byte_cursor + skipped].chars().count();
+                     byte_cursor += skipped;
+                     let offset = char_cursor;
+                     byte_cursor += line.len();
+                     char_cursor += line.chars().count();
+                     (offset, line)
+                 })
+            .collect()
+    }
+
+    fn glyph_index(&self, ch: char) -> u32 {
+        let code = ch as u32;
+        if code >= FIRST_CHAR && code < FIRST_CHAR + NUM_GLYPHS {
+            code - FIRST_CHAR
+        } else {
+            FALLBACK_CHAR as u32 - FIRST_CHAR
+        }
+    }
+}
+
+fn measure_glyph(sheet: &Surface, index: u32) -> Glyph {
+    let cell_x = (index % GLYPH_COLS) * CELL_SIZE;
+    let cell_y = (index / GLYPH_COLS) * CELL_SIZE;
+    let pitch = sheet.pitch() as usize;
+    let lit_columns: Vec<u32> = sheet.with_lock(|pixels| {
+        (0..CELL_SIZE)
+            .filter(|&x| {
+                (0..CELL_SIZE).any(|y| {
+                    // ARGB8888 is stored B, G, R, A
+                    let offset = (cell_y + y) as usize * pitch + (cell_x + x) as usize * 4;
+                    pixels
+                        .get(offset..offset + 3)
+                        .map_or(false, |bgr| bgr.iter().any(|&c| c != 0))
+                })
+            })
+            .collect()
+    });
+
+    match (lit_columns.first(), lit_columns.last()) {
+        (Some(&left), Some(&right)) => {
+            Glyph {
+                left,
+                width: right - left + 1,
+            }
+        }
+        _ => {
+            Glyph {
+                left: 0,
+                width: SPACE_WIDTH,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_offsets_skip_dropped_separators() {
+        let font = Font::without_sheet();
+        // Each glyph is 10 pixels wide with its spacing, so 5 fit on a line
+        let lines = font.wrap_with_offsets("ab  cd\n\nefghijk", 50);
+        let expected = vec![(0, "ab "), (4, "cd"), (6, ""), (8, "efghi"), (13, "jk")];
+        assert_eq!(lines,
+                   expected
+                       .into_iter()
+                       .map(|(offset, line)| (offset, String::from(line)))
+                       .collect::<Vec<(usize, String)>>());
+    }
+}
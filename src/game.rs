@@ -1,12 +1,29 @@
+use audio::Audio;
+use flags::Flags;
+use game_over::{GameOver, GameOverChoice};
 use graphics::Graphics;
+use hud::Hud;
 use input::Input;
-use map::Map;
+use inventory;
+use inventory_screen::{self, InventoryScreen};
+use interaction::InteractionAction;
+use log::Logger;
+use map::{Map, WarpDestination, WarpTrigger};
 use player::Player;
+use profile;
+use save::{self, SaveData};
+use save_menu::{SaveMenu, SaveMenuChoice};
+use script_host::{GameScriptHost, ScriptState};
 use sdl2;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
+use sdl2::rect::Point;
+use stage::{Stage, TEST_STAGE};
 use std::thread::sleep;
 use time::{Duration, PreciseTime};
+use title::{TitleChoice, TitleScreen};
+use transition::Fade;
+use tsc::Interpreter;
 use units::{AsGame, Fps, Tile};
 
 const FPS: Fps = 120;
@@ -19,6 +36,13 @@ pub const SCREEN_HEIGHT: Tile = Tile(15);
 
 pub struct Game {}
 
+struct Warping {
+    fade: Fade,
+    destination: WarpDestination,
+    // Script event to run once the player arrives
+    event: Option<u16>,
+}
+
 impl Game {
     pub fn new() -> Game {
         Game {}
@@ -30,12 +54,27 @@ impl Game {
         let mut event_pump = sdl_context
             .event_pump()
             .expect("Failed to create event pump");
+        let mut audio = Audio::new(&sdl_context);
         let mut graphics: &mut Graphics = &mut Graphics::new(sdl_context)
                                                    .expect("Failed to create graphics");
         let mut input = Input::new();
-        let (width, height) = ((SCREEN_WIDTH / Tile(2)).to_game(), (SCREEN_HEIGHT / Tile(2)).to_game());
-        let mut player = Player::new(graphics, width / 2, height / 2);
-        let mut map = Map::create_test_map(graphics);
+        let mut player = new_player(graphics);
+        let mut flags = Flags::new();
+        let mut show_flags = false;
+        let mut hud = Hud::new();
+        let mut inventory_screen = InventoryScreen::new();
+        let mut save_menu = SaveMenu::new();
+        let mut stage = Stage::load(graphics, TEST_STAGE, &flags).expect("Failed to load stage");
+        let mut warping: Option<Warping> = None;
+        let mut interpreter = Interpreter::new(stage.script().clone());
+        let mut script_state = ScriptState::new();
+        let mut playtime = Duration::zero();
+        let logger = Logger::new("game");
+        // What a retry after dying goes back to, None to start over. Saves from earlier sessions
+        // count too.
+        let mut last_save = latest_save(&logger);
+        let mut game_over: Option<GameOver> = None;
+        let mut title: Option<TitleScreen> = None;
 
         // Prepare
         let mut last_update_time = PreciseTime::now();
@@ -48,6 +87,7 @@ impl Game {
             //     // This loop lasts 1/60th os a second
             //     //                 1000/60ths of a ms
             input.begin_new_frame();
+            stage.map_mut().begin_new_frame();
             let start_ticks = PreciseTime::now();
             for event in event_pump.poll_iter() {
                 match event {
@@ -61,20 +101,306 @@ impl Game {
             if input.was_key_pressed(Keycode::Escape) {
                 break 'running;
             }
+            if input.was_key_pressed(Keycode::F3) {
+                show_flags = !show_flags;
+            }
+
+            let mut restart = false;
+            match title.as_mut().and_then(|title| title.update(&input, &mut audio)) {
+                Some(TitleChoice::Quit) => break 'running,
+                Some(TitleChoice::NewGame) => {
+                    last_save = None;
+                    restart = true;
+                }
+                Some(TitleChoice::Continue) => {
+                    last_save = latest_save(&logger);
+                    restart = true;
+                }
+                None => {}
+            }
+            match game_over
+                      .as_mut()
+                      .and_then(|game_over| {
+                                    game_over.update(last_update_time.to(PreciseTime::now()),
+                                                     &input,
+                                                     &mut audio)
+                                }) {
+                Some(GameOverChoice::Retry) => restart = true,
+                Some(GameOverChoice::QuitToTitle) => {
+                    game_over = None;
+                    title = Some(TitleScreen::new(save::latest_slot().is_some()));
+                }
+                None => {}
+            }
+            let can_save = title.is_none() && warping.is_none() && game_over.is_none() &&
+                           !interpreter.is_running() &&
+                           !script_state.message_box_mut().is_open() &&
+                           !inventory_screen.is_open() && !save_menu.is_open();
+            if input.was_key_pressed(Keycode::F5) && can_save {
+                let data = SaveData::capture(stage.name(), &player, &flags, playtime);
+                match save::save(save::QUICK_SAVE_SLOT, &data) {
+                    Ok(()) => {
+                        logger.log("Quick saved");
+                        last_save = Some(data);
+                    }
+                    Err(e) => logger.log(e),
+                }
+            } else if input.was_key_pressed(Keycode::F9) && can_save {
+                // Loads like a retry, so nothing from the current run carries over
+                match save::load(save::QUICK_SAVE_SLOT) {
+                    Ok(Some(data)) => {
+                        last_save = Some(data);
+                        restart = true;
+                    }
+                    Ok(None) => logger.log("Nothing quick saved yet"),
+                    Err(e) => logger.log(e),
+                }
+            } else if input.was_key_pressed(Keycode::F8) && can_save {
+                // Playtesters' saves from the original game. The import goes to the quick save
+                // slot first, so retrying after dying finds it on disk like any other save.
+                match profile::read(profile::PROFILE_PATH) {
+                    Ok(import) => {
+                        for field in import.unsupported.iter() {
+                            logger.log(format!("Not imported: {}", field));
+                        }
+                        match save::save(save::QUICK_SAVE_SLOT, &import.save) {
+                            Ok(()) => {
+                                last_save = Some(import.save);
+                                restart = true;
+                            }
+                            Err(e) => logger.log(e),
+                        }
+                    }
+                    Err(e) => logger.log(e),
+                }
+            }
+
+            if restart {
+                // The map, its entities and the flags all come back as they were saved
+                match restart_game(graphics, last_save.as_ref(), &mut player, &mut flags) {
+                    Ok((new_stage, saved_playtime)) => {
+                        stage = new_stage;
+                        playtime = saved_playtime;
+                        interpreter.set_script(stage.script().clone());
+                        script_state = ScriptState::new();
+                        warping = None;
+                        hud = Hud::new();
+                        game_over = None;
+                        title = None;
+                        player.resume_input();
+                    }
+                    Err(e) => {
+                        logger.log(e);
+                        // A quick load that fails leaves the game running as it was
+                        if title.is_some() || game_over.is_some() {
+                            game_over = None;
+                            title = Some(TitleScreen::new(save::latest_slot().is_some()));
+                        }
+                    }
+                }
+                last_update_time = PreciseTime::now();
+            }
+            if let Some(ref title) = title {
+                // Nothing else runs on the title screen
+                title.draw(graphics);
+                graphics.flip();
+                last_update_time = PreciseTime::now();
+                self.frame_limit(start_ticks.to(PreciseTime::now()));
+                continue;
+            }
+
+            if input.was_key_pressed(inventory_screen::TOGGLE_KEY) && warping.is_none() &&
+               game_over.is_none() && !interpreter.is_running() &&
+               !script_state.message_box_mut().is_open() && !save_menu.is_open() {
+                if inventory_screen.is_open() {
+                    inventory_screen.close();
+                    player.resume_input();
+                } else {
+                    inventory_screen.open(player.inventory());
+                    player.suspend_input();
+                }
+            }
+            inventory_screen.update(&input, player.inventory_mut(), &mut audio);
+            match save_menu.update(&input, &mut audio) {
+                Some(SaveMenuChoice::Slot(slot)) => {
+                    let data = SaveData::capture(stage.name(), &player, &flags, playtime);
+                    let message = match save::save(slot, &data) {
+                        Ok(()) => {
+                            last_save = Some(data);
+                            String::from("Game saved.")
+                        }
+                        Err(e) => {
+                            logger.log(e);
+                            String::from("The game couldn't be saved.")
+                        }
+                    };
+                    // Input stays suspended until the message is dismissed
+                    script_state.message_box_mut().show(&[message]);
+                }
+                Some(SaveMenuChoice::Cancel) => player.resume_input(),
+                None => {}
+            }
 
             // UPDATE
             let current_time = PreciseTime::now();
-            let elapsed_time = last_update_time.to(current_time);
-            player.update(elapsed_time, &map, &input);
-            map.update(elapsed_time);
+            // The world stands still behind the pause screen and save menu
+            let elapsed_time = if inventory_screen.is_open() || save_menu.is_open() {
+                Duration::zero()
+            } else {
+                last_update_time.to(current_time)
+            };
+            // The player stays where they fell behind the game over screen
+            if game_over.is_none() {
+                player.update(elapsed_time, stage.map(), &input);
+            }
+            stage.map_mut().update(elapsed_time);
+            hud.update(elapsed_time, &player);
+            playtime = playtime + elapsed_time;
             last_update_time = current_time;
+
+            if warping.is_none() && game_over.is_none() {
+                let player_rect = player.collision_rect();
+                let mut destination = stage
+                    .map()
+                    .find_warp(&player_rect, WarpTrigger::Edge)
+                    .map(|warp| warp.destination().clone());
+                match stage.interactions_mut().update(&player) {
+                    Some(InteractionAction::OpenDoor(door_destination)) => {
+                        destination = Some(door_destination);
+                    }
+                    Some(InteractionAction::Dialogue(lines)) => {
+                        player.suspend_input();
+                        script_state.message_box_mut().show(&lines);
+                    }
+                    Some(InteractionAction::GiveItem(item)) => {
+                        player.inventory_mut().add_item(item);
+                        player.suspend_input();
+                        let name = inventory::item_name(item);
+                        let message_box = script_state.message_box_mut();
+                        message_box.show(&[format!("Got the {}!", name)]);
+                        message_box.show_item(Some(String::from(name)));
+                    }
+                    Some(InteractionAction::OpenSaveMenu) => {
+                        player.suspend_input();
+                        save_menu.open();
+                    }
+                    Some(InteractionAction::Refill) => {
+                        player.refill();
+                        player.suspend_input();
+                        script_state
+                            .message_box_mut()
+                            .show(&[String::from("Health and ammo refilled.")]);
+                    }
+                    Some(InteractionAction::RunEvent(event)) => {
+                        if let Err(e) = interpreter.start_event(event) {
+                            logger.log(e);
+                        }
+                    }
+                    None => {}
+                }
+                if let Some(destination) = destination {
+                    player.suspend_input();
+                    warping = Some(Warping {
+                                       fade: Fade::new(),
+                                       destination,
+                                       event: None,
+                                   });
+                }
+            }
+
+            {
+                let mut host = GameScriptHost {
+                    state: &mut script_state,
+                    stage: &mut stage,
+                    player: &mut player,
+                    flags: &mut flags,
+                    input: &input,
+                };
+                for e in interpreter.update(&mut host, elapsed_time) {
+                    logger.log(e);
+                }
+            }
+            for event in player.inventory_mut().take_events() {
+                logger.log(format!("{:?}", event));
+            }
+            if script_state
+                   .message_box_mut()
+                   .update(elapsed_time, &input, &mut audio) {
+                // A dialogue the player dismissed, scripts hand back control themselves
+                player.resume_input();
+            }
+            if game_over.is_none() && player.health() == 0 {
+                // Whatever the player was doing ends with them, including any running event
+                player.suspend_input();
+                inventory_screen.close();
+                save_menu.close();
+                script_state = ScriptState::new();
+                interpreter.set_script(stage.script().clone());
+                game_over = Some(GameOver::new(player.collision_rect().center()));
+            }
+            if let Some(transport) = script_state.take_transport() {
+                player.suspend_input();
+                warping = Some(Warping {
+                                   fade: Fade::new(),
+                                   destination: transport.destination,
+                                   event: Some(transport.event),
+                               });
+            }
+
+            let mut finished_warp = false;
+            if let Some(ref mut warp) = warping {
+                if warp.fade.update(elapsed_time) {
+                    // Screen is black, swap rooms. The old stage and everything in it is dropped.
+                    let destination = &warp.destination;
+                    match Stage::load(graphics, destination.map_name(), &flags) {
+                        Ok(new_stage) => {
+                            stage = new_stage;
+                            interpreter.set_script(stage.script().clone());
+                            player.set_position(Tile(destination.col()).to_game(),
+                                                Tile(destination.row()).to_game());
+                            if let Some(event) = warp.event {
+                                if let Err(e) = interpreter.start_event(event) {
+                                    logger.log(e);
+                                }
+                            }
+                        }
+                        Err(e) => logger.log(e),
+                    }
+                }
+                finished_warp = warp.fade.is_done();
+            }
+            if finished_warp {
+                warping = None;
+                player.resume_input();
+            }
             //
 
+            let camera = camera_position(stage.map(),
+                                         player.collision_rect().center(),
+                                         graphics.screen_size());
+            stage.map_mut().set_camera(camera.x(), camera.y());
+
             // DRAW
             graphics.clear();
-            map.draw_background(graphics);
-            player.draw(&mut graphics);
-            map.draw(graphics);
+            stage.map_mut().draw_background(graphics);
+            stage.interactions().draw(graphics, camera);
+            if game_over.is_none() {
+                player.draw(&mut graphics, camera);
+            }
+            stage.map_mut().draw_foreground(graphics);
+            hud.draw(graphics, &player);
+            script_state.message_box_mut().draw(graphics);
+            inventory_screen.draw(graphics, player.inventory());
+            save_menu.draw(graphics);
+            if let Some(ref game_over) = game_over {
+                game_over.draw(graphics, camera);
+            }
+            if let Some(ref warp) = warping {
+                warp.fade.draw(graphics);
+            }
+            if show_flags {
+                flags.draw_overlay(graphics);
+            }
             graphics.flip();
             //
 
@@ -92,3 +418,66 @@ impl Game {
         }
     }
 }
+
+// Loads the saved map, then puts the player and flags back as they were. Nothing changes if the
+// map fails to load.
+fn load_save(graphics: &mut Graphics,
+             save: &SaveData,
+             player: &mut Player,
+             flags: &mut Flags)
+             -> Result<Stage, String> {
+    // Flags decide which entities the stage leaves out, so they're restored first
+    let saved_flags = save.restore_flags();
+    let stage = Stage::load(graphics, &save.map, &saved_flags)?;
+    *flags = saved_flags;
+    save.restore_player(player);
+    Ok(stage)
+}
+
+// The most recent save on disk, if any
+fn latest_save(logger: &Logger) -> Option<SaveData> {
+    match save::latest_slot().map(save::load) {
+        Some(Ok(data)) => data,
+        Some(Err(e)) => {
+            logger.log(e);
+            None
+        }
+        None => None,
+    }
+}
+
+// Top left of the screen in map pixels, centered on `focus` but kept inside the map. Maps smaller
+// than the screen stay at the top left.
+fn camera_position(map: &Map, focus: Point, screen_size: (u32, u32)) -> Point {
+    let (screen_width, screen_height) = screen_size;
+    let map_width = (map.num_cols() as u32 * TILE_SIZE) as i32;
+    let map_height = (map.num_rows() as u32 * TILE_SIZE) as i32;
+    let max_x = (map_width - screen_width as i32).max(0);
+    let max_y = (map_height - screen_height as i32).max(0);
+    Point::new((focus.x() - screen_width as i32 / 2).max(0).min(max_x),
+               (focus.y() - screen_height as i32 / 2).max(0).min(max_y))
+}
+
+fn new_player(graphics: &mut Graphics) -> Player {
+    let (width, height) = ((SCREEN_WIDTH / Tile(2)).to_game(), (SCREEN_HEIGHT / Tile(2)).to_game());
+    Player::new(graphics, width / 2, height / 2)
+}
+
+// Back to the last save, or a new game when there isn't one. Returns the stage to play and the
+// playtime to count on from.
+fn restart_game(graphics: &mut Graphics,
+                save: Option<&SaveData>,
+                player: &mut Player,
+                flags: &mut Flags)
+                -> Result<(Stage, Duration), String> {
+    match save {
+        Some(save) => Ok((load_save(graphics, save, player, flags)?, save.playtime())),
+        None => {
+            let new_flags = Flags::new();
+            let stage = Stage::load(graphics, TEST_STAGE, &new_flags)?;
+            *flags = new_flags;
+            *player = new_player(graphics);
+            Ok((stage, Duration::zero()))
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use audio::Audio;
+use graphics::Graphics;
+use input::Input;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use std::f32::consts::PI;
+use time::Duration;
+
+// The player bursts into pieces, then the screen darkens before the menu shows
+const DEATH_ANIMATION_MS: i64 = 1200;
+const NUM_PIECES: usize = 12;
+const PIECE_SPEED: f32 = 0.12; // pixels/ms
+const PIECE_SIZE: u32 = 6;
+const DARKEN_MS: i64 = 400;
+
+const OPTIONS: &[&str] = &["Retry", "Quit to title"];
+
+const TEXT_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const CURSOR_COLOR: Color = Color {
+    r: 255,
+    g: 220,
+    b: 0,
+    a: 255,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameOverChoice {
+    // Back to the last save, or the start if there isn't one
+    Retry,
+    QuitToTitle,
+}
+
+// Runs from the moment the player's health hits zero until they choose what to do next. The
+// player isn't drawn meanwhile, only the pieces they burst into.
+pub struct GameOver {
+    // Where the player was, in map pixels
+    center: Point,
+    elapsed: Duration,
+    cursor: usize,
+}
+
+impl GameOver {
+    pub fn new(center: Point) -> GameOver {
+        GameOver {
+            center,
+            elapsed: Duration::zero(),
+            cursor: 0,
+        }
+    }
+
+    fn is_menu_shown(&self) -> bool {
+        self.elapsed >= Duration::milliseconds(DEATH_ANIMATION_MS + DARKEN_MS)
+    }
+
+    pub fn update(&mut self,
+                  elapsed_time: Duration,
+                  input: &Input,
+                  audio: &mut Audio)
+                  -> Option<GameOverChoice> {
+        if !self.is_menu_shown() {
+            self.elapsed = self.elapsed + elapsed_time;
+            return None;
+        }
+
+        if input.was_key_pressed(Keycode::Up) || input.was_key_pressed(Keycode::Down) {
+            self.cursor = (self.cursor + 1) % OPTIONS.len();
+            audio.play_cursor();
+        }
+        if !input.was_key_pressed(Keycode::Z) {
+            return None;
+        }
+        match self.cursor {
+            0 => Some(GameOverChoice::Retry),
+            _ => Some(GameOverChoice::QuitToTitle),
+        }
+    }
+
+    // `camera` is the top left of the screen in map pixels
+    pub fn draw(&self, graphics: &mut Graphics, camera: Point) {
+        let elapsed_ms = self.elapsed.num_milliseconds();
+        if elapsed_ms < DEATH_ANIMATION_MS {
+            let progress = elapsed_ms as f32 / DEATH_ANIMATION_MS as f32;
+            let distance = elapsed_ms as f32 * PIECE_SPEED;
+            for i in 0..NUM_PIECES {
+                let angle = i as f32 * 2.0 * PI / NUM_PIECES as f32;
+                let x = self.center.x() - camera.x() + (angle.cos() * distance) as i32;
+                let y = self.center.y() - camera.y() + (angle.sin() * distance) as i32;
+                graphics.fill_rect(Rect::from_center(Point::new(x, y), PIECE_SIZE, PIECE_SIZE),
+                                   Color::RGBA(255, 255, 255, (255.0 * (1.0 - progress)) as u8));
+            }
+        }
+
+        let darkness = ((elapsed_ms - DEATH_ANIMATION_MS) as f32 / DARKEN_MS as f32)
+            .max(0.0)
+            .min(1.0);
+        if darkness > 0.0 {
+            graphics.fill_screen(Color::RGBA(0, 0, 0, (darkness * 200.0) as u8));
+        }
+        if !self.is_menu_shown() {
+            return;
+        }
+
+        let (screen_width, screen_height) = graphics.screen_size();
+        let line_height = graphics.font().line_height() as i32;
+        let title = "GAME OVER";
+        let x = (screen_width - graphics.font().text_width(title)) as i32 / 2;
+        let mut y = screen_height as i32 / 2 - 2 * line_height;
+        graphics.draw_text(title, x, y, TEXT_COLOR);
+        y += 2 * line_height;
+        for (i, option) in OPTIONS.iter().enumerate() {
+            let color = if i == self.cursor {
+                CURSOR_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            let x = (screen_width - graphics.font().text_width(option)) as i32 / 2;
+            graphics.draw_text(option, x, y, color);
+            y += line_height;
+        }
+    }
+}
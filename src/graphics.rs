@@ -1,18 +1,35 @@
+use font::{Font, FONT_PATH};
 use game::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use log::Logger;
 use sdl2::Sdl;
 use sdl2::pixels::Color;
-use sdl2::render::WindowCanvas;
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
 use sdl2::rect::Rect;
 use sdl2::surface::Surface;
+use sdl2::video::WindowContext;
 use std::collections::HashMap;
 
 pub struct Graphics {
     screen: WindowCanvas,
+    // Leaked so textures can be kept around for as long as the game runs, e.g. by the map's tile
+    // cache. There is only ever one.
+    texture_creator: &'static TextureCreator<WindowContext>,
     sprite_sheets: HashMap<String, Surface<'static>>,
+    font: Font,
+    // Tinted per call to draw_text. None without a font sheet, when glyphs are drawn as blocks.
+    font_texture: Option<Texture<'static>>,
 }
 
 impl Graphics {
     pub fn new(sdl_context: Sdl) -> Result<Graphics, String> {
+        // Missing font art shouldn't stop the game from starting
+        let font = match Font::load(FONT_PATH) {
+            Ok(font) => font,
+            Err(e) => {
+                Logger::new("graphics").log(format!("Drawing text as blocks: {}", e));
+                Font::without_sheet()
+            }
+        };
         sdl_context.mouse().show_cursor(false);
         sdl_context
             .video()
@@ -21,7 +38,6 @@ impl Graphics {
                 video_subsystem
                     .window("Cave Story: Rust", SCREEN_WIDTH, SCREEN_HEIGHT)
                     .position_centered()
-                    .opengl()
                     .build()
                     .map_err(|e| e.to_string())
                     .and_then(|window| {
@@ -33,10 +49,17 @@ impl Graphics {
                             .and_then(|mut canvas| {
                                           canvas.clear();
                                           canvas.present();
-                                          Ok(Graphics {
-                                                 screen: canvas,
-                                                 sprite_sheets: HashMap::new(),
-                                             })
+                                          let texture_creator =
+                                              Box::leak(Box::new(canvas.texture_creator()));
+                                          let mut graphics = Graphics {
+                                              screen: canvas,
+                                              texture_creator,
+                                              sprite_sheets: HashMap::new(),
+                                              font,
+                                              font_texture: None,
+                                          };
+                                          graphics.font_texture = graphics.load_font_texture();
+                                          Ok(graphics)
                                       })
                     })
             })
@@ -58,20 +81,151 @@ impl Graphics {
             })
     }
 
+    // For optional art such as face portraits: None instead of a panic when the file is missing
+    pub fn try_load_image(&mut self, file_path: &str) -> Option<&Surface<'static>> {
+        if !self.sprite_sheets.contains_key(file_path) {
+            let mut surface = match Surface::load_bmp(file_path) {
+                Ok(surface) => surface,
+                Err(_) => return None,
+            };
+            surface
+                .set_color_key(true, Color::RGB(0, 0, 0))
+                .expect("Failed to key sprite");
+            self.sprite_sheets.insert(String::from(file_path), surface);
+        }
+        self.sprite_sheets.get(file_path)
+    }
+
     pub fn blit_surface<S, D>(&mut self, src_id: &str, source_rect: S, dest_rect: D)
         where S: Into<Option<Rect>>,
               D: Into<Option<Rect>>
     {
-        let surface = self.sprite_sheets.get_mut(src_id).unwrap();
-        let texture_creator = self.screen.texture_creator();
+        let surface = self.sprite_sheets.get(src_id).unwrap();
+        Graphics::copy_surface(&mut self.screen, surface, source_rect, dest_rect);
+    }
+
+    // Uploads a surface once so it can be drawn every frame without converting it again
+    pub fn create_texture(&self, surface: &Surface) -> Texture<'static> {
+        self.texture_creator
+            .create_texture_from_surface(surface)
+            .expect("Failed to create texture")
+    }
+
+    // Draws a texture made by `create_texture`, e.g. an offscreen cache
+    pub fn draw_texture<S, D>(&mut self, texture: &Texture, source_rect: S, dest_rect: D)
+        where S: Into<Option<Rect>>,
+              D: Into<Option<Rect>>
+    {
+        self.screen
+            .copy(texture, source_rect.into(), dest_rect.into())
+            .expect("Failed to copy texture");
+    }
+
+    pub fn sprite_sheet(&self, file_path: &str) -> Option<&Surface<'static>> {
+        self.sprite_sheets.get(file_path)
+    }
+
+    fn copy_surface<S, D>(screen: &mut WindowCanvas,
+                          surface: &Surface,
+                          source_rect: S,
+                          dest_rect: D)
+        where S: Into<Option<Rect>>,
+              D: Into<Option<Rect>>
+    {
+        let texture_creator = screen.texture_creator();
         let texture = texture_creator
             .create_texture_from_surface(surface)
             .expect("Failed to create texture");
-        self.screen
+        screen
             .copy(&texture, source_rect.into(), dest_rect.into())
             .expect("Failed to copy texture");
     }
 
+    pub fn screen_size(&self) -> (u32, u32) {
+        self.screen.output_size().expect("Failed to get screen size")
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.screen.set_blend_mode(BlendMode::Blend);
+        self.screen.set_draw_color(color);
+        self.screen.fill_rect(rect).expect("Failed to fill rect");
+        self.screen.set_draw_color(Color::RGB(0, 0, 0));
+    }
+
+    pub fn fill_rects(&mut self, rects: &[Rect], color: Color) {
+        self.screen.set_blend_mode(BlendMode::Blend);
+        self.screen.set_draw_color(color);
+        self.screen.fill_rects(rects).expect("Failed to fill rects");
+        self.screen.set_draw_color(Color::RGB(0, 0, 0));
+    }
+
+    pub fn fill_screen(&mut self, color: Color) {
+        let (width, height) = self.screen_size();
+        self.fill_rect(Rect::new(0, 0, width, height), color);
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    // Draws one line of text with its top left corner at (x, y), tinted `color`. Returns the
+    // width drawn.
+    pub fn draw_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> u32 {
+        if let Some(ref mut texture) = self.font_texture {
+            texture.set_color_mod(color.r, color.g, color.b);
+            texture.set_alpha_mod(color.a);
+        } else {
+            self.screen.set_draw_color(color);
+            self.screen.set_blend_mode(BlendMode::Blend);
+        }
+
+        let mut dest_x = x;
+        for ch in text.chars() {
+            let (source_rect, advance) = self.font.glyph(ch);
+            let dest_rect = Rect::new(dest_x, y, source_rect.width(), source_rect.height());
+            match self.font_texture {
+                Some(ref texture) => {
+                    self.screen
+                        .copy(texture, source_rect, dest_rect)
+                        .expect("Failed to copy texture")
+                }
+                None if ch != ' ' => {
+                    let block = Rect::new(dest_x,
+                                          y + 2,
+                                          source_rect.width(),
+                                          source_rect.height() - 4);
+                    self.screen.fill_rect(block).expect("Failed to fill rect")
+                }
+                None => {}
+            }
+            dest_x += advance as i32;
+        }
+        (dest_x - x) as u32
+    }
+
+    fn load_font_texture(&mut self) -> Option<Texture<'static>> {
+        let sheet_path = match self.font.sheet_path() {
+            Some(sheet_path) => String::from(sheet_path),
+            None => return None,
+        };
+        self.load_image(&sheet_path, true);
+        let texture = self.texture_creator
+            .create_texture_from_surface(&self.sprite_sheets[&sheet_path])
+            .expect("Failed to create texture");
+        Some(texture)
+    }
+
+    // Word wraps text inside `area`, dropping lines that don't fit. Returns the lines drawn.
+    pub fn draw_text_wrapped(&mut self, text: &str, area: Rect, color: Color) -> usize {
+        let line_height = self.font.line_height();
+        let max_lines = (area.height() / line_height) as usize;
+        let lines = self.font.wrap(text, area.width());
+        for (i, line) in lines.iter().take(max_lines).enumerate() {
+            self.draw_text(line, area.x(), area.y() + (i as u32 * line_height) as i32, color);
+        }
+        lines.len().min(max_lines)
+    }
+
     pub fn clear(&mut self) {
         self.screen.clear();
     }
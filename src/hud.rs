@@ -0,0 +1,137 @@
+use graphics::Graphics;
+use player::Player;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use time::Duration;
+use weapon::Weapon;
+
+// Weapon icons are 16x16 in the original, drawn at double size in one row
+const ICON_PATH: &str = "content/ArmsImage.bmp";
+const ICON_SIZE: u32 = 32;
+
+const MARGIN: i32 = 16;
+const BAR_X: i32 = 80;
+const BAR_WIDTH: u32 = 80;
+const BAR_HEIGHT: u32 = 10;
+const AMMO_Y: i32 = MARGIN;
+const LEVEL_Y: i32 = MARGIN + ICON_SIZE as i32 + 4;
+const HEALTH_Y: i32 = LEVEL_Y + 22;
+
+// Lost health lingers on the bar for a moment before draining away
+const DRAIN_DELAY_MS: i64 = 400;
+const DRAIN_MS_PER_POINT: f32 = 40.0;
+
+const TEXT_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const BAR_BACKGROUND: Color = Color {
+    r: 40,
+    g: 20,
+    b: 20,
+    a: 255,
+};
+const HEALTH_COLOR: Color = Color {
+    r: 220,
+    g: 30,
+    b: 30,
+    a: 255,
+};
+const DRAIN_COLOR: Color = Color {
+    r: 255,
+    g: 200,
+    b: 200,
+    a: 255,
+};
+const EXP_COLOR: Color = Color {
+    r: 255,
+    g: 200,
+    b: 0,
+    a: 255,
+};
+
+// Health and weapon status, drawn in screen space over everything in the world
+pub struct Hud {
+    // Health the bar still shows while it drains down to the real value
+    shown_health: f32,
+    drain_delay: Duration,
+    icons_missing: bool,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            shown_health: 0.0,
+            drain_delay: Duration::zero(),
+            icons_missing: false,
+        }
+    }
+
+    pub fn update(&mut self, elapsed_time: Duration, player: &Player) {
+        let health = player.health() as f32;
+        if health >= self.shown_health {
+            self.shown_health = health;
+            self.drain_delay = Duration::milliseconds(DRAIN_DELAY_MS);
+        } else if self.drain_delay > Duration::zero() {
+            self.drain_delay = self.drain_delay - elapsed_time;
+        } else {
+            let drained = elapsed_time.num_milliseconds() as f32 / DRAIN_MS_PER_POINT;
+            self.shown_health = (self.shown_health - drained).max(health);
+        }
+    }
+
+    pub fn draw(&mut self, graphics: &mut Graphics, player: &Player) {
+        if let Some(weapon) = player.current_weapon() {
+            self.draw_weapon(graphics, weapon);
+        }
+
+        let max_health = player.max_health().max(1) as f32;
+        let bar = Rect::new(BAR_X, HEALTH_Y, BAR_WIDTH, BAR_HEIGHT);
+        graphics.fill_rect(bar, BAR_BACKGROUND);
+        fill_fraction(graphics, bar, self.shown_health / max_health, DRAIN_COLOR);
+        fill_fraction(graphics, bar, player.health() as f32 / max_health, HEALTH_COLOR);
+        graphics.draw_text(&player.health().to_string(), MARGIN, HEALTH_Y - 4, TEXT_COLOR);
+    }
+
+    fn draw_weapon(&mut self, graphics: &mut Graphics, weapon: &Weapon) {
+        let icon_rect = Rect::new(MARGIN, MARGIN, ICON_SIZE, ICON_SIZE);
+        if !self.icons_missing && graphics.try_load_image(ICON_PATH).is_some() {
+            let source_rect =
+                Rect::new((weapon.id() as u32 * ICON_SIZE) as i32, 0, ICON_SIZE, ICON_SIZE);
+            graphics.blit_surface(ICON_PATH, source_rect, icon_rect);
+        } else {
+            // No icon art, show the start of the weapon's name instead
+            self.icons_missing = true;
+            graphics.fill_rect(icon_rect, BAR_BACKGROUND);
+            let initials: String = weapon.name().chars().take(2).collect();
+            graphics.draw_text(&initials, MARGIN + 4, MARGIN + 8, TEXT_COLOR);
+        }
+
+        let ammo = if weapon.has_ammo_limit() {
+            format!("{}/{}", weapon.ammo(), weapon.max_ammo())
+        } else {
+            String::from("--/--")
+        };
+        graphics.draw_text(&ammo, BAR_X, AMMO_Y, TEXT_COLOR);
+
+        graphics.draw_text(&format!("Lv {}", weapon.level()), MARGIN, LEVEL_Y, TEXT_COLOR);
+        let bar = Rect::new(BAR_X, LEVEL_Y + 4, BAR_WIDTH, BAR_HEIGHT);
+        graphics.fill_rect(bar, BAR_BACKGROUND);
+        if weapon.is_max_level() {
+            fill_fraction(graphics, bar, 1.0, EXP_COLOR);
+            graphics.draw_text("MAX", bar.right() + 6, LEVEL_Y, EXP_COLOR);
+        } else {
+            let exp_for_level = weapon.exp_for_level().max(1) as f32;
+            fill_fraction(graphics, bar, weapon.exp() as f32 / exp_for_level, EXP_COLOR);
+        }
+    }
+}
+
+fn fill_fraction(graphics: &mut Graphics, bar: Rect, fraction: f32, color: Color) {
+    let width = (bar.width() as f32 * fraction.max(0.0).min(1.0)) as u32;
+    if width > 0 {
+        graphics.fill_rect(Rect::new(bar.x(), bar.y(), width, bar.height()), color);
+    }
+}
@@ -0,0 +1,253 @@
+use game::TILE_SIZE;
+use graphics::Graphics;
+use map::{EntityPlacement, Warp, WarpDestination};
+use player::Player;
+use sdl2::rect::{Point, Rect};
+use sprite::{Sprite, StaticSprite};
+
+// Map entity kinds the game handles itself rather than through a script
+pub const SAVE_POINT: &str = "save_point";
+pub const REFILL_STATION: &str = "refill_station";
+
+// Sprites for the objects above, one TILE_SIZE frame each
+const OBJECT_SHEET_PATH: &str = "content/NpcSym.bmp";
+const SAVE_POINT_FRAME: i32 = 0;
+const REFILL_STATION_FRAME: i32 = 1;
+
+// What the game should do in response to an interaction
+#[derive(Clone, Debug, PartialEq)]
+pub enum InteractionAction {
+    Dialogue(Vec<String>),
+    OpenDoor(WarpDestination),
+    GiveItem(u16),
+    RunEvent(u16),
+    OpenSaveMenu,
+    // Restore health and ammo
+    Refill,
+}
+
+pub trait Interactable {
+    // Area in pixels the player has to be in to interact
+    fn area(&self) -> Rect;
+    fn interact(&mut self, player: &Player) -> Option<InteractionAction>;
+
+    // Most objects are drawn by the map's tiles and have nothing of their own to draw. `camera` is
+    // the top left of the screen in map pixels.
+    fn draw(&self, _graphics: &mut Graphics, _camera: Point) {}
+}
+
+pub struct Sign {
+    area: Rect,
+    lines: Vec<String>,
+}
+
+impl Sign {
+    pub fn new(area: Rect, lines: Vec<String>) -> Sign {
+        Sign { area, lines }
+    }
+}
+
+impl Interactable for Sign {
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn interact(&mut self, _player: &Player) -> Option<InteractionAction> {
+        Some(InteractionAction::Dialogue(self.lines.clone()))
+    }
+}
+
+// Stays shut while the player is missing the door's key item
+pub struct Door {
+    area: Rect,
+    destination: WarpDestination,
+    required_item: u16,
+}
+
+impl Door {
+    pub fn from_warp(warp: &Warp) -> Door {
+        Door {
+            area: warp.area(),
+            destination: warp.destination().clone(),
+            required_item: warp.required_item(),
+        }
+    }
+}
+
+impl Interactable for Door {
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn interact(&mut self, player: &Player) -> Option<InteractionAction> {
+        if self.required_item != 0 && !player.has_item(self.required_item) {
+            return Some(InteractionAction::Dialogue(vec![String::from("It's locked.")]));
+        }
+        Some(InteractionAction::OpenDoor(self.destination.clone()))
+    }
+}
+
+// Gives its item the first time it's opened and is empty afterwards
+pub struct Chest {
+    area: Rect,
+    item: Option<u16>,
+}
+
+impl Chest {
+    pub fn new(area: Rect, item: u16) -> Chest {
+        Chest {
+            area,
+            item: Some(item),
+        }
+    }
+}
+
+impl Interactable for Chest {
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn interact(&mut self, _player: &Player) -> Option<InteractionAction> {
+        self.item.take().map(InteractionAction::GiveItem)
+    }
+}
+
+// Runs a script event, which is how NPCs and most placed objects respond
+pub struct Scripted {
+    area: Rect,
+    event: u16,
+}
+
+impl Scripted {
+    pub fn from_placement(placement: &EntityPlacement) -> Scripted {
+        Scripted {
+            area: tile_area(placement.row(), placement.col()),
+            event: placement.event(),
+        }
+    }
+}
+
+impl Interactable for Scripted {
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn interact(&mut self, _player: &Player) -> Option<InteractionAction> {
+        Some(InteractionAction::RunEvent(self.event))
+    }
+}
+
+// A floppy disk the player saves at
+pub struct SavePoint {
+    area: Rect,
+    sprite: StaticSprite,
+}
+
+impl SavePoint {
+    pub fn from_placement(graphics: &mut Graphics, placement: &EntityPlacement) -> SavePoint {
+        SavePoint {
+            area: tile_area(placement.row(), placement.col()),
+            sprite: object_sprite(graphics, SAVE_POINT_FRAME),
+        }
+    }
+}
+
+impl Interactable for SavePoint {
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn interact(&mut self, _player: &Player) -> Option<InteractionAction> {
+        Some(InteractionAction::OpenSaveMenu)
+    }
+
+    fn draw(&self, graphics: &mut Graphics, camera: Point) {
+        self.sprite.draw(graphics, self.area.x() - camera.x(), self.area.y() - camera.y());
+    }
+}
+
+// A heart-shaped machine that tops the player up, as often as they like
+pub struct RefillStation {
+    area: Rect,
+    sprite: StaticSprite,
+}
+
+impl RefillStation {
+    pub fn from_placement(graphics: &mut Graphics, placement: &EntityPlacement) -> RefillStation {
+        RefillStation {
+            area: tile_area(placement.row(), placement.col()),
+            sprite: object_sprite(graphics, REFILL_STATION_FRAME),
+        }
+    }
+}
+
+impl Interactable for RefillStation {
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn interact(&mut self, _player: &Player) -> Option<InteractionAction> {
+        Some(InteractionAction::Refill)
+    }
+
+    fn draw(&self, graphics: &mut Graphics, camera: Point) {
+        self.sprite.draw(graphics, self.area.x() - camera.x(), self.area.y() - camera.y());
+    }
+}
+
+fn object_sprite(graphics: &mut Graphics, frame: i32) -> StaticSprite {
+    StaticSprite::new(graphics,
+                      OBJECT_SHEET_PATH,
+                      frame * TILE_SIZE as i32,
+                      0,
+                      TILE_SIZE,
+                      TILE_SIZE)
+}
+
+pub fn tile_area(row: usize, col: usize) -> Rect {
+    Rect::new((col as u32 * TILE_SIZE) as i32,
+              (row as u32 * TILE_SIZE) as i32,
+              TILE_SIZE,
+              TILE_SIZE)
+}
+
+pub struct Interactions {
+    objects: Vec<Box<Interactable>>,
+    was_interacting: bool,
+}
+
+impl Interactions {
+    pub fn new() -> Interactions {
+        Interactions {
+            objects: vec![],
+            was_interacting: false,
+        }
+    }
+
+    pub fn add(&mut self, object: Box<Interactable>) {
+        self.objects.push(object);
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics, camera: Point) {
+        for object in self.objects.iter() {
+            object.draw(graphics, camera);
+        }
+    }
+
+    // Fires the handler of the object the player is in front of, once per press of down. Where
+    // objects overlap, the first one with something to do wins, so e.g. an opened chest doesn't
+    // hide a sign behind it.
+    pub fn update(&mut self, player: &Player) -> Option<InteractionAction> {
+        let pressed = player.is_interacting() && !self.was_interacting;
+        self.was_interacting = player.is_interacting();
+        if !pressed {
+            return None;
+        }
+
+        let player_rect = player.collision_rect();
+        self.objects
+            .iter_mut()
+            .filter(|object| object.area().has_intersection(player_rect))
+            .find_map(|object| object.interact(player))
+    }
+}
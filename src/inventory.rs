@@ -0,0 +1,322 @@
+use weapon::Weapon;
+
+struct ItemInfo {
+    name: &'static str,
+    description: &'static str,
+}
+
+// Key items by the number scripts use, in the original game's order
+const ITEMS: &[ItemInfo] = &[ItemInfo {
+                                 name: "",
+                                 description: "",
+                             },
+                             ItemInfo {
+                                 name: "Arthur's Key",
+                                 description: "A key left behind by a hero of the village.",
+                             },
+                             ItemInfo {
+                                 name: "Map System",
+                                 description: "Shows a map of the area you're in.",
+                             },
+                             ItemInfo {
+                                 name: "Santa's Key",
+                                 description: "The key to Santa's house.",
+                             },
+                             ItemInfo {
+                                 name: "Silver Locket",
+                                 description: "A locket found in a cave.",
+                             },
+                             ItemInfo {
+                                 name: "Beast Fang",
+                                 description: "A fang from some huge beast.",
+                             },
+                             ItemInfo {
+                                 name: "Life Capsule",
+                                 description: "Raises maximum health.",
+                             },
+                             ItemInfo {
+                                 name: "ID Card",
+                                 description: "Opens the door to the Egg Observation Room.",
+                             },
+                             ItemInfo {
+                                 name: "Jellyfish Juice",
+                                 description: "Said to put out fires.",
+                             },
+                             ItemInfo {
+                                 name: "Rusty Key",
+                                 description: "A key gone red with rust.",
+                             },
+                             ItemInfo {
+                                 name: "Gum Key",
+                                 description: "The key to the Gum room.",
+                             },
+                             ItemInfo {
+                                 name: "Gum Base",
+                                 description: "The raw base for chewing gum.",
+                             },
+                             ItemInfo {
+                                 name: "Charcoal",
+                                 description: "A lump of charcoal.",
+                             },
+                             ItemInfo {
+                                 name: "Explosive",
+                                 description: "Handle with care.",
+                             },
+                             ItemInfo {
+                                 name: "Puppy",
+                                 description: "A small, sleepy puppy.",
+                             },
+                             ItemInfo {
+                                 name: "Life Pot",
+                                 description: "Restores all health when used.",
+                             },
+                             ItemInfo {
+                                 name: "Cure-All",
+                                 description: "A medicine that cures anything.",
+                             },
+                             ItemInfo {
+                                 name: "Clinic Key",
+                                 description: "The key to the clinic.",
+                             },
+                             ItemInfo {
+                                 name: "Booster 0.8",
+                                 description: "Hold jump in the air to fly for a short time.",
+                             },
+                             ItemInfo {
+                                 name: "Arms Barrier",
+                                 description: "Halves the experience lost when you are hurt.",
+                             },
+                             ItemInfo {
+                                 name: "Turbocharge",
+                                 description: "Makes the Machine Gun recharge faster.",
+                             },
+                             ItemInfo {
+                                 name: "Curly's Air Tank",
+                                 description: "Lets you breathe underwater.",
+                             },
+                             ItemInfo {
+                                 name: "Nikumaru Counter",
+                                 description: "Keeps time.",
+                             },
+                             ItemInfo {
+                                 name: "Booster 2.0",
+                                 description: "Hold jump and a direction to fly that way.",
+                             },
+                             ItemInfo {
+                                 name: "Mimiga Mask",
+                                 description: "A mask that looks like a Mimiga.",
+                             },
+                             ItemInfo {
+                                 name: "Teleporter Room Key",
+                                 description: "The key to the teleporter room.",
+                             },
+                             ItemInfo {
+                                 name: "Sue's Letter",
+                                 description: "A letter from Sue.",
+                             },
+                             ItemInfo {
+                                 name: "Controller",
+                                 description: "The controller for a broken robot.",
+                             },
+                             ItemInfo {
+                                 name: "Broken Sprinkler",
+                                 description: "It doesn't work.",
+                             },
+                             ItemInfo {
+                                 name: "Sprinkler",
+                                 description: "Keeps the plants watered.",
+                             },
+                             ItemInfo {
+                                 name: "Tow Rope",
+                                 description: "A sturdy rope.",
+                             },
+                             ItemInfo {
+                                 name: "Medal of the Red Ogre",
+                                 description: "Proof of a famous victory.",
+                             },
+                             ItemInfo {
+                                 name: "Mister Little",
+                                 description: "A very small man.",
+                             },
+                             ItemInfo {
+                                 name: "Mushroom Badge",
+                                 description: "A badge shaped like a mushroom.",
+                             },
+                             ItemInfo {
+                                 name: "Ma Pignon",
+                                 description: "A mushroom with big ambitions.",
+                             },
+                             ItemInfo {
+                                 name: "Little Man",
+                                 description: "Someone is missing him.",
+                             },
+                             ItemInfo {
+                                 name: "Iron Bond",
+                                 description: "A heavy iron ring.",
+                             },
+                             ItemInfo {
+                                 name: "Clay Figure Medal",
+                                 description: "A medal made of clay.",
+                             },
+                             ItemInfo {
+                                 name: "Chaco's Lipstick",
+                                 description: "Chaco's favourite lipstick.",
+                             }];
+
+pub fn is_known_item(id: u16) -> bool {
+    id != 0 && (id as usize) < ITEMS.len()
+}
+
+pub fn item_name(id: u16) -> &'static str {
+    ITEMS.get(id as usize).map_or("", |info| info.name)
+}
+
+pub fn item_description(id: u16) -> &'static str {
+    ITEMS.get(id as usize).map_or("", |info| info.description)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InventoryEvent {
+    ItemAdded(u16),
+    ItemRemoved(u16),
+    WeaponAdded(u16),
+    WeaponRemoved(u16),
+    WeaponSelected(u16),
+}
+
+// Everything the player carries. Weapons are kept in the order the player switches through
+// them. Changes are queued as events for whoever wants to react to them.
+pub struct Inventory {
+    items: Vec<u16>,
+    weapons: Vec<Weapon>,
+    current_weapon: usize,
+    events: Vec<InventoryEvent>,
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        Inventory {
+            items: vec![],
+            weapons: vec![],
+            current_weapon: 0,
+            events: vec![],
+        }
+    }
+
+    // Rebuilds a saved inventory without queuing any events
+    pub fn from_parts(items: Vec<u16>, weapons: Vec<Weapon>, current_weapon: usize) -> Inventory {
+        Inventory {
+            items,
+            current_weapon: current_weapon.min(weapons.len().saturating_sub(1)),
+            weapons,
+            events: vec![],
+        }
+    }
+
+    pub fn items(&self) -> &[u16] {
+        &self.items
+    }
+
+    pub fn has_item(&self, id: u16) -> bool {
+        self.items.contains(&id)
+    }
+
+    // Key items are only ever held once
+    pub fn add_item(&mut self, id: u16) {
+        if !self.has_item(id) {
+            self.items.push(id);
+            self.events.push(InventoryEvent::ItemAdded(id));
+        }
+    }
+
+    pub fn remove_item(&mut self, id: u16) {
+        if let Some(position) = self.items.iter().position(|&item| item == id) {
+            self.items.remove(position);
+            self.events.push(InventoryEvent::ItemRemoved(id));
+        }
+    }
+
+    pub fn weapons(&self) -> &[Weapon] {
+        &self.weapons
+    }
+
+    pub fn has_weapon(&self, id: u16) -> bool {
+        self.weapons.iter().any(|weapon| weapon.id() == id)
+    }
+
+    // Picking up a weapon the player already has just raises its ammo limit
+    pub fn add_weapon(&mut self, id: u16, max_ammo: u16) {
+        match self.weapons.iter_mut().find(|weapon| weapon.id() == id) {
+            Some(weapon) => weapon.add_max_ammo(max_ammo),
+            None => {
+                self.weapons.push(Weapon::new(id, max_ammo));
+                self.events.push(InventoryEvent::WeaponAdded(id));
+            }
+        }
+    }
+
+    pub fn remove_weapon(&mut self, id: u16) {
+        if let Some(position) = self.weapons.iter().position(|weapon| weapon.id() == id) {
+            self.weapons.remove(position);
+            if self.current_weapon > position ||
+               self.current_weapon >= self.weapons.len() && self.current_weapon > 0 {
+                self.current_weapon -= 1;
+            }
+            self.events.push(InventoryEvent::WeaponRemoved(id));
+        }
+    }
+
+    pub fn refill_ammo(&mut self) {
+        for weapon in self.weapons.iter_mut() {
+            weapon.refill_ammo();
+        }
+    }
+
+    pub fn current_weapon(&self) -> Option<&Weapon> {
+        self.weapons.get(self.current_weapon)
+    }
+
+    pub fn current_weapon_mut(&mut self) -> Option<&mut Weapon> {
+        self.weapons.get_mut(self.current_weapon)
+    }
+
+    pub fn current_weapon_index(&self) -> usize {
+        self.current_weapon
+    }
+
+    pub fn select_weapon(&mut self, index: usize) {
+        if index < self.weapons.len() && index != self.current_weapon {
+            self.current_weapon = index;
+            self.events.push(InventoryEvent::WeaponSelected(self.weapons[index].id()));
+        }
+    }
+
+    // Switching wraps around the weapon order, `step` is 1 for next and -1 for previous
+    pub fn cycle_weapon(&mut self, step: i32) {
+        let len = self.weapons.len() as i32;
+        if len > 1 {
+            let index = (self.current_weapon as i32 + step + len) % len;
+            self.select_weapon(index as usize);
+        }
+    }
+
+    // Changes the switch order, keeping the same weapon in hand
+    pub fn move_weapon(&mut self, from: usize, to: usize) {
+        if from >= self.weapons.len() || to >= self.weapons.len() || from == to {
+            return;
+        }
+        let current_id = self.current_weapon().map(|weapon| weapon.id());
+        let weapon = self.weapons.remove(from);
+        self.weapons.insert(to, weapon);
+        if let Some(id) = current_id {
+            self.current_weapon = self.weapons
+                .iter()
+                .position(|weapon| weapon.id() == id)
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<InventoryEvent> {
+        ::std::mem::replace(&mut self.events, vec![])
+    }
+}
@@ -0,0 +1,233 @@
+use audio::Audio;
+use graphics::Graphics;
+use input::Input;
+use inventory::{self, Inventory};
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+pub const TOGGLE_KEY: Keycode = Keycode::Q;
+
+const PANEL_WIDTH: u32 = 576;
+const PANEL_HEIGHT: u32 = 384;
+const PADDING: i32 = 16;
+const WEAPON_SLOT_WIDTH: u32 = 108;
+const WEAPON_SLOT_HEIGHT: u32 = 48;
+const WEAPONS_PER_ROW: usize = 5;
+const ITEM_SLOT_WIDTH: u32 = 136;
+const ITEM_SLOT_HEIGHT: u32 = 24;
+const ITEMS_PER_ROW: usize = 4;
+const DESCRIPTION_HEIGHT: u32 = 56;
+
+const PANEL_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 40,
+    a: 240,
+};
+const TEXT_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const HEADING_COLOR: Color = Color {
+    r: 140,
+    g: 160,
+    b: 255,
+    a: 255,
+};
+const CURSOR_COLOR: Color = Color {
+    r: 255,
+    g: 220,
+    b: 0,
+    a: 255,
+};
+const EQUIPPED_COLOR: Color = Color {
+    r: 60,
+    g: 60,
+    b: 120,
+    a: 255,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Section {
+    Weapons,
+    Items,
+}
+
+// The pause screen listing weapons and key items. Z takes a weapon in hand, X picks it up to
+// move it along the switch order and X again puts it down.
+pub struct InventoryScreen {
+    open: bool,
+    section: Section,
+    cursor: usize,
+    moving_weapon: bool,
+}
+
+impl InventoryScreen {
+    pub fn new() -> InventoryScreen {
+        InventoryScreen {
+            open: false,
+            section: Section::Weapons,
+            cursor: 0,
+            moving_weapon: false,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, inventory: &Inventory) {
+        self.open = true;
+        self.section = Section::Weapons;
+        self.cursor = inventory.current_weapon_index();
+        self.moving_weapon = false;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.moving_weapon = false;
+    }
+
+    pub fn update(&mut self, input: &Input, inventory: &mut Inventory, audio: &mut Audio) {
+        if !self.open {
+            return;
+        }
+        let (len, row_len) = match self.section {
+            Section::Weapons => (inventory.weapons().len(), WEAPONS_PER_ROW),
+            Section::Items => (inventory.items().len(), ITEMS_PER_ROW),
+        };
+
+        let (mut section, mut cursor) = (self.section, self.cursor);
+        if input.was_key_pressed(Keycode::Left) && len > 0 {
+            cursor = (cursor + len - 1) % len;
+        } else if input.was_key_pressed(Keycode::Right) && len > 0 {
+            cursor = (cursor + 1) % len;
+        } else if input.was_key_pressed(Keycode::Up) {
+            if cursor >= row_len {
+                cursor -= row_len;
+            } else if section == Section::Items && !inventory.weapons().is_empty() {
+                section = Section::Weapons;
+                cursor = inventory.current_weapon_index();
+            }
+        } else if input.was_key_pressed(Keycode::Down) {
+            if cursor + row_len < len {
+                cursor += row_len;
+            } else if section == Section::Weapons && !inventory.items().is_empty() {
+                section = Section::Items;
+                cursor = 0;
+            }
+        }
+
+        if self.moving_weapon && section != self.section {
+            // A weapon being moved stays among the weapons
+            section = self.section;
+            cursor = self.cursor;
+        }
+        if (section, cursor) != (self.section, self.cursor) {
+            if self.moving_weapon {
+                inventory.move_weapon(self.cursor, cursor);
+            }
+            self.section = section;
+            self.cursor = cursor;
+            audio.play_cursor();
+        }
+
+        if self.section == Section::Weapons && self.cursor < inventory.weapons().len() {
+            if input.was_key_pressed(Keycode::Z) && !self.moving_weapon {
+                inventory.select_weapon(self.cursor);
+            } else if input.was_key_pressed(Keycode::X) {
+                self.moving_weapon = !self.moving_weapon;
+            }
+        }
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics, inventory: &Inventory) {
+        if !self.open {
+            return;
+        }
+        let (screen_width, screen_height) = graphics.screen_size();
+        let panel = Rect::new((screen_width - PANEL_WIDTH) as i32 / 2,
+                              (screen_height - PANEL_HEIGHT) as i32 / 2,
+                              PANEL_WIDTH,
+                              PANEL_HEIGHT);
+        graphics.fill_rect(panel, PANEL_COLOR);
+        let line_height = graphics.font().line_height() as i32;
+
+        let mut y = panel.y() + PADDING;
+        graphics.draw_text("ARMS", panel.x() + PADDING, y, HEADING_COLOR);
+        y += line_height + 4;
+        for (i, weapon) in inventory.weapons().iter().enumerate() {
+            let slot = Rect::new(panel.x() + PADDING +
+                                 (i % WEAPONS_PER_ROW) as i32 * WEAPON_SLOT_WIDTH as i32,
+                                 y + (i / WEAPONS_PER_ROW) as i32 * WEAPON_SLOT_HEIGHT as i32,
+                                 WEAPON_SLOT_WIDTH - 4,
+                                 WEAPON_SLOT_HEIGHT - 4);
+            if i == inventory.current_weapon_index() {
+                graphics.fill_rect(slot, EQUIPPED_COLOR);
+            }
+            let color = if self.section == Section::Weapons && i == self.cursor {
+                CURSOR_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            let name = graphics.font().wrap(weapon.name(), slot.width() - 4);
+            graphics.draw_text(name.first().map_or("", |line| line.as_str()),
+                               slot.x() + 2,
+                               slot.y() + 2,
+                               color);
+            let status = if weapon.has_ammo_limit() {
+                format!("Lv{} {}/{}", weapon.level(), weapon.ammo(), weapon.max_ammo())
+            } else {
+                format!("Lv{} --/--", weapon.level())
+            };
+            graphics.draw_text(&status, slot.x() + 2, slot.y() + 2 + line_height, color);
+        }
+        let weapon_rows = (inventory.weapons().len() + WEAPONS_PER_ROW - 1) / WEAPONS_PER_ROW;
+        y += weapon_rows.max(1) as i32 * WEAPON_SLOT_HEIGHT as i32 + 8;
+
+        graphics.draw_text("ITEM", panel.x() + PADDING, y, HEADING_COLOR);
+        y += line_height + 4;
+        for (i, &item) in inventory.items().iter().enumerate() {
+            let color = if self.section == Section::Items && i == self.cursor {
+                CURSOR_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            graphics.draw_text(inventory::item_name(item),
+                               panel.x() + PADDING +
+                               (i % ITEMS_PER_ROW) as i32 * ITEM_SLOT_WIDTH as i32,
+                               y + (i / ITEMS_PER_ROW) as i32 * ITEM_SLOT_HEIGHT as i32,
+                               color);
+        }
+
+        let description = match self.section {
+            Section::Weapons if self.moving_weapon => {
+                String::from("Left and right move the weapon, X puts it down.")
+            }
+            Section::Weapons => {
+                inventory
+                    .weapons()
+                    .get(self.cursor)
+                    .map_or(String::new(), |weapon| {
+                        format!("{}. Z to equip, X to move it in the switch order.",
+                                weapon.name())
+                    })
+            }
+            Section::Items => {
+                inventory
+                    .items()
+                    .get(self.cursor)
+                    .map_or(String::new(),
+                            |&item| String::from(inventory::item_description(item)))
+            }
+        };
+        let description_area = Rect::new(panel.x() + PADDING,
+                                          panel.bottom() - DESCRIPTION_HEIGHT as i32,
+                                          PANEL_WIDTH - 2 * PADDING as u32,
+                                          DESCRIPTION_HEIGHT - PADDING as u32);
+        graphics.draw_text_wrapped(&description, description_area, TEXT_COLOR);
+    }
+}
@@ -0,0 +1,4 @@
+// Parts of the game that the tools in src/bin share with it
+extern crate time;
+
+pub mod tsc;
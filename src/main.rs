@@ -1,17 +1,38 @@
 #![feature(step_by)]
+#![cfg_attr(test, feature(test))]
 
+extern crate cave_story;
 extern crate sdl2;
 extern crate time;
 
+mod audio;
 mod backdrop;
+mod flags;
+mod font;
 mod game;
+mod game_over;
 mod graphics;
+mod hud;
 mod input;
+mod interaction;
+mod inventory;
+mod inventory_screen;
 mod log;
 mod map;
+mod message_box;
 mod player;
+mod profile;
+mod save;
+mod save_menu;
+mod script_host;
 mod sprite;
+mod stage;
+mod title;
+mod transition;
 mod units;
+mod weapon;
+
+use cave_story::tsc;
 
 fn main() {
     game::Game::new().play();
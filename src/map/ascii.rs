@@ -0,0 +1,106 @@
+use map::{Layer, Map, ENTITY_DRAW_ORDER};
+use map::tile::{Tile, TileIndex, TileType};
+use std::collections::HashMap;
+
+// Characters used by `Map::from_ascii`. Starts out with `#` as a wall and `.` as air.
+pub struct AsciiLegend {
+    tiles: HashMap<char, Tile>,
+}
+
+impl AsciiLegend {
+    pub fn new() -> AsciiLegend {
+        let mut tiles = HashMap::new();
+        tiles.insert('#', Tile::with_type(TileType::Wall));
+        tiles.insert('.', Tile::new());
+        AsciiLegend { tiles }
+    }
+
+    pub fn with<I>(mut self, symbol: char, tile_type: TileType, index: I) -> AsciiLegend
+        where I: Into<Option<TileIndex>>
+    {
+        let tile = match index.into() {
+            Some(index) => Tile::from_index(index, tile_type),
+            None => Tile::with_type(tile_type),
+        };
+        self.tiles.insert(symbol, tile);
+        self
+    }
+
+    pub fn tile(&self, symbol: char) -> Option<Tile> {
+        self.tiles.get(&symbol).cloned()
+    }
+}
+
+impl Map {
+    // Builds a single colliding "main" layer from one line of text per row. Surrounding
+    // whitespace and blank lines are ignored so the art can be indented inline.
+    //
+    //   Map::from_ascii("#....#
+    //                    #.##.#
+    //                    ######",
+    //                   &AsciiLegend::new())
+    pub fn from_ascii(art: &str, legend: &AsciiLegend) -> Result<Map, String> {
+        let rows: Vec<&str> = art.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let num_rows = rows.len();
+        let num_cols = rows.first().map_or(0, |row| row.chars().count());
+
+        let mut map = Map::new();
+        map.num_rows = num_rows;
+        map.num_cols = num_cols;
+        let mut layer = Layer::new("main", num_rows, num_cols)
+            .with_draw_order(ENTITY_DRAW_ORDER - 1)
+            .with_collision(true);
+        for (row, line) in rows.iter().enumerate() {
+            if line.chars().count() != num_cols {
+                return Err(format!("Row {} is {} tiles wide, expected {}",
+                                   row,
+                                   line.chars().count(),
+                                   num_cols));
+            }
+            for (col, symbol) in line.chars().enumerate() {
+                let tile = legend
+                    .tile(symbol)
+                    .ok_or_else(|| {
+                                    format!("No tile for '{}' at row {}, col {}", symbol, row, col)
+                                })?;
+                *layer.tile_mut(row, col).unwrap() = tile;
+            }
+        }
+        map.add_layer(layer);
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_are_looked_up_in_the_legend() {
+        let legend = AsciiLegend::new().with('^', TileType::Spike, 57);
+        let map = Map::from_ascii("#.^
+                                   ###",
+                                  &legend)
+                .unwrap();
+
+        assert_eq!((map.num_rows(), map.num_cols()), (2, 3));
+        assert_eq!(map.tile(0, 0, 0), Some(&Tile::with_type(TileType::Wall)));
+        assert_eq!(map.tile(0, 0, 1), Some(&Tile::new()));
+        assert_eq!(map.tile(0, 0, 2), Some(&Tile::from_index(57, TileType::Spike)));
+        assert!(map.layers()[0].collides());
+    }
+
+    #[test]
+    fn ragged_rows_are_an_error() {
+        assert!(Map::from_ascii("###\n##", &AsciiLegend::new()).is_err());
+    }
+
+    #[test]
+    fn unknown_symbols_are_an_error() {
+        assert!(Map::from_ascii("#?#", &AsciiLegend::new()).is_err());
+    }
+}
@@ -0,0 +1,472 @@
+// Plain text map format. Each line is a directive followed by its arguments, and each `layer`
+// line is followed by one line of tiles per map row. A tile is `-` for no graphic or a tileset
+// index, with `:wall` or `:air` appended when its type differs from the tileset's.
+//
+//   version 4
+//   size 15 20
+//   tileset content/PrtCave.bmp
+//   tile_type 1 wall
+//   animation 48 8 per_instance 48 49 50
+//   backdrop content/bkBlue.bmp
+//   boundary wall
+//   layer main -1 1 true
+//   1 1 1 - - ...
+//   entity sign 13 18 100 0
+//   trigger 0 19 15 1 200
+//   warp door 12 18 2 1 first_cave 5 3 0
+//   warp edge 0 20 15 1 hallway 12 1
+//
+// Lines starting with // are comments. Directives added in later versions are optional, so
+// older files keep loading. Names and paths can't contain whitespace; maps holding one are
+// refused on save rather than written in a form that won't load back.
+
+use graphics::Graphics;
+use map::{EntityPlacement, Layer, Map, TriggerPlacement};
+use map::warp::{Warp, WarpDestination, WarpTrigger};
+use map::tile::{Tile, TileIndex, TileType};
+use map::tile_animation::{AnimationPhase, TileAnimation};
+use map::tileset::Tileset;
+use std::fmt::Write;
+use std::str::FromStr;
+
+// 2: warp directive
+// 3: entity flag
+// 4: warp required item
+pub const MAP_FORMAT_VERSION: u32 = 4;
+
+pub fn write(map: &Map) -> Result<String, String> {
+    let mut out = String::new();
+    writeln!(out, "version {}", MAP_FORMAT_VERSION).unwrap();
+    writeln!(out, "size {} {}", map.num_rows, map.num_cols).unwrap();
+
+    if let Some(ref tileset) = map.tileset {
+        writeln!(out, "tileset {}", word("Tileset path", tileset.sheet_path())?).unwrap();
+        for index in 0..tileset.len() as TileIndex {
+            let attributes = tileset.attributes(index).unwrap();
+            if attributes.tile_type() != TileType::Air {
+                writeln!(out, "tile_type {} {}", index, tile_type_name(attributes.tile_type()))
+                    .unwrap();
+            }
+            if let Some(animation) = attributes.animation() {
+                let frames: Vec<String> =
+                    animation.frames().iter().map(|frame| frame.to_string()).collect();
+                writeln!(out,
+                         "animation {} {} {} {}",
+                         index,
+                         animation.fps(),
+                         phase_name(animation.phase()),
+                         frames.join(" "))
+                        .unwrap();
+            }
+        }
+    }
+    if let Some(ref backdrop_path) = map.backdrop_path {
+        writeln!(out, "backdrop {}", word("Backdrop path", backdrop_path)?).unwrap();
+    }
+    writeln!(out, "boundary {}", tile_type_name(map.boundary_tile_type)).unwrap();
+
+    for layer in map.layers.iter() {
+        writeln!(out,
+                 "layer {} {} {} {}",
+                 word("Layer name", layer.name())?,
+                 layer.draw_order(),
+                 layer.parallax(),
+                 layer.collides())
+                .unwrap();
+        for row in 0..layer.num_rows() {
+            let cells: Vec<String> = (0..layer.num_cols())
+                .map(|col| tile_token(map.tileset.as_ref(), layer.tile(row, col).unwrap()))
+                .collect();
+            writeln!(out, "{}", cells.join(" ")).unwrap();
+        }
+    }
+
+    for entity in map.entities.iter() {
+        writeln!(out,
+                 "entity {} {} {} {} {}",
+                 word("Entity kind", entity.kind())?,
+                 entity.row(),
+                 entity.col(),
+                 entity.event(),
+                 entity.flag())
+                .unwrap();
+    }
+    for trigger in map.triggers.iter() {
+        writeln!(out,
+                 "trigger {} {} {} {} {}",
+                 trigger.row(),
+                 trigger.col(),
+                 trigger.num_rows(),
+                 trigger.num_cols(),
+                 trigger.event())
+                .unwrap();
+    }
+    for warp in map.warps.iter() {
+        writeln!(out,
+                 "warp {} {} {} {} {} {} {} {} {}",
+                 warp_trigger_name(warp.trigger()),
+                 warp.row(),
+                 warp.col(),
+                 warp.num_rows(),
+                 warp.num_cols(),
+                 word("Warp destination", warp.destination().map_name())?,
+                 warp.destination().row(),
+                 warp.destination().col(),
+                 warp.required_item())
+                .unwrap();
+    }
+
+    Ok(out)
+}
+
+pub fn read(graphics: &mut Graphics, text: &str) -> Result<Map, String> {
+    let mut map = parse(text, |path| Tileset::new(graphics, path))?;
+    if let Some(path) = map.backdrop_path.clone() {
+        map.set_backdrop(graphics, &path);
+    }
+    Ok(map)
+}
+
+// Reads everything but the backdrop image, which is only recorded by path. Tilesets come from
+// `load_tileset`.
+fn parse<F>(text: &str, mut load_tileset: F) -> Result<Map, String>
+    where F: FnMut(&str) -> Result<Tileset, String>
+{
+    let mut map = Map::new();
+    let mut has_size = false;
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|&(_, line)| !line.is_empty() && !line.starts_with("//"));
+
+    while let Some((number, line)) = lines.next() {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let at_line = |message: String| format!("Line {}: {}", number, message);
+        match args[0] {
+            "version" => {
+                let version: u32 = parse_arg(&args, 1).map_err(&at_line)?;
+                if version > MAP_FORMAT_VERSION {
+                    return Err(at_line(format!("Map format version {} is newer than the \
+                                                supported version {}",
+                                               version,
+                                               MAP_FORMAT_VERSION)));
+                }
+            }
+            "size" => {
+                // Layers already read would no longer match
+                if has_size {
+                    return Err(at_line(String::from("The map size is given twice")));
+                }
+                map.num_rows = parse_arg(&args, 1).map_err(&at_line)?;
+                map.num_cols = parse_arg(&args, 2).map_err(&at_line)?;
+                has_size = true;
+            }
+            "tileset" => {
+                let path = string_arg(&args, 1).map_err(&at_line)?;
+                map.set_tileset(load_tileset(path).map_err(&at_line)?);
+            }
+            "tile_type" => {
+                let index: TileIndex = parse_arg(&args, 1).map_err(&at_line)?;
+                let tile_type = parse_tile_type(string_arg(&args, 2).map_err(&at_line)?)
+                    .map_err(&at_line)?;
+                tileset_arg(&mut map, index).map_err(&at_line)?.set_tile_type(index, tile_type);
+            }
+            "animation" => {
+                let index: TileIndex = parse_arg(&args, 1).map_err(&at_line)?;
+                let fps = parse_arg(&args, 2).map_err(&at_line)?;
+                let phase = parse_phase(string_arg(&args, 3).map_err(&at_line)?)
+                    .map_err(&at_line)?;
+                let frames = (4..args.len())
+                    .map(|position| parse_arg(&args, position))
+                    .collect::<Result<Vec<TileIndex>, String>>()
+                    .map_err(&at_line)?;
+                if frames.is_empty() {
+                    return Err(at_line(String::from("Animation has no frames")));
+                }
+                tileset_arg(&mut map, index)
+                    .map_err(&at_line)?
+                    .set_animation(index, TileAnimation::new(frames, fps, phase));
+            }
+            "backdrop" => {
+                map.backdrop_path = Some(String::from(string_arg(&args, 1).map_err(&at_line)?));
+            }
+            "boundary" => {
+                let tile_type = parse_tile_type(string_arg(&args, 1).map_err(&at_line)?)
+                    .map_err(&at_line)?;
+                map.set_boundary_tile_type(tile_type);
+            }
+            "layer" => {
+                if !has_size {
+                    return Err(at_line(String::from("Layer given before the map size")));
+                }
+                let (num_rows, num_cols) = (map.num_rows, map.num_cols);
+                let mut layer = Layer::new(string_arg(&args, 1).map_err(&at_line)?,
+                                           num_rows,
+                                           num_cols)
+                        .with_draw_order(parse_arg(&args, 2).map_err(&at_line)?)
+                        .with_parallax(parse_arg(&args, 3).map_err(&at_line)?)
+                        .with_collision(parse_arg(&args, 4).map_err(&at_line)?);
+                for row in 0..num_rows {
+                    let (number, line) = lines
+                        .next()
+                        .ok_or_else(|| at_line(format!("Layer is missing row {}", row)))?;
+                    let cells: Vec<&str> = line.split_whitespace().collect();
+                    if cells.len() != num_cols {
+                        return Err(format!("Line {}: Expected {} tiles but found {}",
+                                           number,
+                                           num_cols,
+                                           cells.len()));
+                    }
+                    for (col, cell) in cells.iter().enumerate() {
+                        *layer.tile_mut(row, col).unwrap() =
+                            parse_tile(map.tileset.as_ref(), cell)
+                                .map_err(|message| format!("Line {}: {}", number, message))?;
+                    }
+                }
+                map.add_layer(layer);
+            }
+            "entity" => {
+                let entity = EntityPlacement::new(string_arg(&args, 1).map_err(&at_line)?,
+                                                  parse_arg(&args, 2).map_err(&at_line)?,
+                                                  parse_arg(&args, 3).map_err(&at_line)?,
+                                                  parse_arg(&args, 4).map_err(&at_line)?);
+                let flag = if args.len() > 5 {
+                    parse_arg(&args, 5).map_err(&at_line)?
+                } else {
+                    0
+                };
+                map.entities.push(entity.with_flag(flag));
+            }
+            "trigger" => {
+                let trigger = TriggerPlacement::new(parse_arg(&args, 1).map_err(&at_line)?,
+                                                    parse_arg(&args, 2).map_err(&at_line)?,
+                                                    parse_arg(&args, 3).map_err(&at_line)?,
+                                                    parse_arg(&args, 4).map_err(&at_line)?,
+                                                    parse_arg(&args, 5).map_err(&at_line)?);
+                map.triggers.push(trigger);
+            }
+            "warp" => {
+                let trigger = parse_warp_trigger(string_arg(&args, 1).map_err(&at_line)?)
+                    .map_err(&at_line)?;
+                let destination = WarpDestination::new(string_arg(&args, 6).map_err(&at_line)?,
+                                                       parse_arg(&args, 7).map_err(&at_line)?,
+                                                       parse_arg(&args, 8).map_err(&at_line)?);
+                let required_item = if args.len() > 9 {
+                    parse_arg(&args, 9).map_err(&at_line)?
+                } else {
+                    0
+                };
+                let warp = Warp::new(trigger,
+                                     parse_arg(&args, 2).map_err(&at_line)?,
+                                     parse_arg(&args, 3).map_err(&at_line)?,
+                                     parse_arg(&args, 4).map_err(&at_line)?,
+                                     parse_arg(&args, 5).map_err(&at_line)?,
+                                     destination);
+                map.warps.push(warp.with_required_item(required_item));
+            }
+            directive => return Err(at_line(format!("Unknown directive {}", directive))),
+        }
+    }
+
+    Ok(map)
+}
+
+fn tileset_arg(map: &mut Map, index: TileIndex) -> Result<&mut Tileset, String> {
+    match map.tileset {
+        Some(ref mut tileset) if (index as usize) < tileset.len() => Ok(tileset),
+        Some(_) => Err(format!("Tile index {} is outside of the tileset", index)),
+        None => Err(String::from("Tile attributes given before the tileset")),
+    }
+}
+
+// Names and paths are written as a single word so they read back as one argument
+fn word<'a>(what: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err(format!("{} \"{}\" can't be saved, it must be one word", what, value));
+    }
+    Ok(value)
+}
+
+fn string_arg<'a>(args: &[&'a str], position: usize) -> Result<&'a str, String> {
+    args.get(position)
+        .cloned()
+        .ok_or_else(|| format!("{} is missing argument {}", args[0], position))
+}
+
+fn parse_arg<T: FromStr>(args: &[&str], position: usize) -> Result<T, String> {
+    let arg = string_arg(args, position)?;
+    arg.parse()
+        .map_err(|_| format!("Invalid argument {} to {}: {}", position, args[0], arg))
+}
+
+fn tile_type_name(tile_type: TileType) -> &'static str {
+    match tile_type {
+        TileType::Air => "air",
+        TileType::Wall => "wall",
+        TileType::Spike => "spike",
+    }
+}
+
+fn parse_tile_type(name: &str) -> Result<TileType, String> {
+    match name {
+        "air" => Ok(TileType::Air),
+        "wall" => Ok(TileType::Wall),
+        "spike" => Ok(TileType::Spike),
+        _ => Err(format!("Unknown tile type {}", name)),
+    }
+}
+
+fn phase_name(phase: AnimationPhase) -> &'static str {
+    match phase {
+        AnimationPhase::Synchronized => "synchronized",
+        AnimationPhase::PerInstance => "per_instance",
+    }
+}
+
+fn parse_phase(name: &str) -> Result<AnimationPhase, String> {
+    match name {
+        "synchronized" => Ok(AnimationPhase::Synchronized),
+        "per_instance" => Ok(AnimationPhase::PerInstance),
+        _ => Err(format!("Unknown animation phase {}", name)),
+    }
+}
+
+fn warp_trigger_name(trigger: WarpTrigger) -> &'static str {
+    match trigger {
+        WarpTrigger::Door => "door",
+        WarpTrigger::Edge => "edge",
+    }
+}
+
+fn parse_warp_trigger(name: &str) -> Result<WarpTrigger, String> {
+    match name {
+        "door" => Ok(WarpTrigger::Door),
+        "edge" => Ok(WarpTrigger::Edge),
+        _ => Err(format!("Unknown warp trigger {}", name)),
+    }
+}
+
+fn default_tile_type(tileset: Option<&Tileset>, index: Option<TileIndex>) -> TileType {
+    match (tileset, index) {
+        (Some(tileset), Some(index)) => tileset.tile(index).tile_type(),
+        _ => TileType::Air,
+    }
+}
+
+fn tile_token(tileset: Option<&Tileset>, tile: &Tile) -> String {
+    let mut token = match tile.index() {
+        Some(index) => index.to_string(),
+        None => String::from("-"),
+    };
+    if tile.tile_type() != default_tile_type(tileset, tile.index()) {
+        token.push(':');
+        token.push_str(tile_type_name(tile.tile_type()));
+    }
+    token
+}
+
+fn parse_tile(tileset: Option<&Tileset>, token: &str) -> Result<Tile, String> {
+    let mut parts = token.splitn(2, ':');
+    let index = match parts.next().unwrap() {
+        "-" => None,
+        index => Some(index.parse::<TileIndex>().map_err(|_| format!("Invalid tile {}", token))?),
+    };
+    let tile_type = match parts.next() {
+        Some(name) => parse_tile_type(name)?,
+        None => default_tile_type(tileset, index),
+    };
+    Ok(match index {
+           Some(index) => Tile::from_index(index, tile_type),
+           None => Tile::with_type(tile_type),
+       })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::ENTITY_DRAW_ORDER;
+
+    const TILESET_PATH: &str = "content/PrtCave.bmp";
+
+    // Sheets aren't loaded in tests, PrtCave.bmp is 16x5 tiles
+    fn parse_text(text: &str) -> Result<Map, String> {
+        parse(text, |path| Ok(Tileset::with_size(path, 16, 5)))
+    }
+
+    fn sample_map() -> Map {
+        let mut map = Map::new();
+        map.num_rows = 3;
+        map.num_cols = 4;
+        let mut tileset = Tileset::with_size(TILESET_PATH, 16, 5);
+        tileset.set_tile_type(1, TileType::Wall);
+        tileset.set_tile_type(57, TileType::Spike);
+        tileset.set_animation(48,
+                              TileAnimation::new(vec![48, 49, 50], 8, AnimationPhase::PerInstance));
+        map.tileset = Some(tileset);
+        map.backdrop_path = Some(String::from("content/bkBlue.bmp"));
+        map.set_boundary_tile_type(TileType::Air);
+
+        let mut main = Layer::new("main", 3, 4)
+            .with_draw_order(ENTITY_DRAW_ORDER - 1)
+            .with_collision(true);
+        *main.tile_mut(2, 0).unwrap() = Tile::from_index(1, TileType::Wall);
+        *main.tile_mut(2, 1).unwrap() = Tile::from_index(1, TileType::Air);
+        *main.tile_mut(1, 3).unwrap() = Tile::from_index(48, TileType::Air);
+        *main.tile_mut(0, 0).unwrap() = Tile::with_type(TileType::Wall);
+        *main.tile_mut(0, 1).unwrap() = Tile::from_index(57, TileType::Spike);
+        map.add_layer(main);
+        map.add_layer(Layer::new("foreground", 3, 4)
+                          .with_draw_order(ENTITY_DRAW_ORDER + 1)
+                          .with_parallax(0.5));
+
+        map.add_entity(EntityPlacement::new("sign", 1, 2, 100).with_flag(7));
+        map.add_trigger(TriggerPlacement::new(0, 3, 3, 1, 200));
+        map.add_warp(Warp::new(WarpTrigger::Door,
+                               1,
+                               1,
+                               1,
+                               1,
+                               WarpDestination::new("first_cave", 5, 3))
+                             .with_required_item(2));
+        map.add_warp(Warp::new(WarpTrigger::Edge,
+                               0,
+                               4,
+                               3,
+                               1,
+                               WarpDestination::new("hallway", 2, 1)));
+        map
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let map = sample_map();
+        let text = write(&map).unwrap();
+        let loaded = parse_text(&text).unwrap();
+
+        assert_eq!(write(&loaded).unwrap(), text);
+        assert_eq!((loaded.num_rows(), loaded.num_cols()), (3, 4));
+        assert_eq!(loaded.layers().len(), 2);
+        assert_eq!(loaded.tile(0, 2, 1), Some(&Tile::from_index(1, TileType::Air)));
+        assert_eq!(loaded.tile(0, 0, 0), Some(&Tile::with_type(TileType::Wall)));
+        assert_eq!(loaded.tile(0, 0, 1), Some(&Tile::from_index(57, TileType::Spike)));
+        assert!(loaded.tileset().unwrap().is_animated(48));
+        assert_eq!(loaded.entities(), map.entities());
+        assert_eq!(loaded.triggers(), map.triggers());
+        assert_eq!(loaded.warps(), map.warps());
+        assert_eq!(loaded.boundary_tile_type(), TileType::Air);
+    }
+
+    #[test]
+    fn names_with_whitespace_are_refused_on_save() {
+        let mut map = sample_map();
+        map.add_entity(EntityPlacement::new("save point", 0, 0, 0));
+        assert!(write(&map).is_err());
+    }
+
+    #[test]
+    fn layers_need_one_size_first() {
+        assert!(parse_text("version 4\nlayer main -1 1 true\n-\n").is_err());
+        assert!(parse_text("version 4\nsize 1 1\nsize 2 2\n").is_err());
+        assert!(parse_text("version 4\nsize 1 1\nlayer main -1 1 true\n-\n").is_ok());
+    }
+}
@@ -0,0 +1,79 @@
+use map::tile::Tile;
+use std::iter::repeat;
+
+// Layers with a draw order below this are drawn behind the player and other entities, the rest
+// are drawn over them.
+pub const ENTITY_DRAW_ORDER: i32 = 0;
+
+pub struct Layer {
+    name: String,
+    tiles: Vec<Vec<Tile>>,
+    draw_order: i32,
+    parallax: f32,
+    collides: bool,
+}
+
+impl Layer {
+    pub fn new(name: &str, num_rows: usize, num_cols: usize) -> Layer {
+        let blank_row: Vec<Tile> = repeat(Tile::new()).take(num_cols).collect();
+        Layer {
+            name: String::from(name),
+            tiles: repeat(blank_row).take(num_rows).collect(),
+            draw_order: ENTITY_DRAW_ORDER - 1,
+            parallax: 1.0,
+            collides: false,
+        }
+    }
+
+    pub fn with_draw_order(mut self, draw_order: i32) -> Layer {
+        self.draw_order = draw_order;
+        self
+    }
+
+    // 1.0 scrolls with the map, smaller values scroll slower to look further away
+    pub fn with_parallax(mut self, parallax: f32) -> Layer {
+        self.parallax = parallax;
+        self
+    }
+
+    pub fn with_collision(mut self, collides: bool) -> Layer {
+        self.collides = collides;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn draw_order(&self) -> i32 {
+        self.draw_order
+    }
+
+    pub fn parallax(&self) -> f32 {
+        self.parallax
+    }
+
+    pub fn collides(&self) -> bool {
+        self.collides
+    }
+
+    pub fn is_above_entities(&self) -> bool {
+        self.draw_order >= ENTITY_DRAW_ORDER
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.tiles.first().map_or(0, |row| row.len())
+    }
+
+    pub fn tile(&self, row: usize, col: usize) -> Option<&Tile> {
+        self.tiles.get(row).and_then(|tiles| tiles.get(col))
+    }
+
+    pub fn tile_mut(&mut self, row: usize, col: usize) -> Option<&mut Tile> {
+        self.tiles.get_mut(row).and_then(|tiles| tiles.get_mut(col))
+    }
+}
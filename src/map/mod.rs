@@ -1,164 +1,436 @@
+mod ascii;
 mod collision_tile;
+mod format;
+mod layer;
+mod pathfinding;
+mod placement;
+mod raycast;
 pub mod tile;
+mod tile_animation;
+mod tile_cache;
+mod tile_change;
+mod tileset;
+mod warp;
 
 use backdrop::{Backdrop, FixedBackdrop};
 use game::{SCREEN_WIDTH, SCREEN_HEIGHT, TILE_SIZE};
 use graphics::Graphics;
-use sdl2::rect::Rect;
-use sprite::{Sprite, StaticSprite};
+use sdl2::rect::{Point, Rect};
+pub use self::ascii::AsciiLegend;
 use self::tile::*;
 use self::collision_tile::*;
-use std::cell::RefCell;
-use std::iter::repeat;
-use std::rc::Rc;
+use self::tile_cache::TileCache;
+use std::fs::File;
+use std::io::{Read, Write};
+pub use self::format::MAP_FORMAT_VERSION;
+pub use self::layer::{Layer, ENTITY_DRAW_ORDER};
+pub use self::pathfinding::{PathAction, PathMode, PathStep, Pathfinder};
+pub use self::placement::{EntityPlacement, TriggerPlacement};
+pub use self::raycast::RaycastHit;
+pub use self::tile_animation::{AnimationPhase, TileAnimation};
+pub use self::tile_change::TileChange;
+pub use self::tileset::{TileAttributes, Tileset};
+pub use self::warp::{Warp, WarpDestination, WarpTrigger};
 use time::Duration;
 
 pub struct Map {
-    tiles: Vec<Vec<Tile>>,
-    background_tiles: Vec<Vec<Tile>>,
+    num_rows: usize,
+    num_cols: usize,
+    layers: Vec<Layer>,
+    draw_sequence: Vec<usize>,
+    camera: Point,
     backdrop: Option<Box<Backdrop>>,
+    backdrop_path: Option<String>,
+    entities: Vec<EntityPlacement>,
+    triggers: Vec<TriggerPlacement>,
+    warps: Vec<Warp>,
+    changed_tiles: Vec<TileChange>,
+    tileset: Option<Tileset>,
+    tile_cache: TileCache,
+    animation_clock: Duration,
+    boundary_tile_type: TileType,
 }
 
 impl Map {
     pub fn new() -> Map {
         Map {
-            tiles: vec![vec![]],
-            background_tiles: vec![vec![]],
+            num_rows: 0,
+            num_cols: 0,
+            layers: vec![],
+            draw_sequence: vec![],
+            camera: Point::new(0, 0),
             backdrop: None,
+            backdrop_path: None,
+            entities: vec![],
+            triggers: vec![],
+            warps: vec![],
+            changed_tiles: vec![],
+            tileset: None,
+            tile_cache: TileCache::new(),
+            animation_clock: Duration::zero(),
+            boundary_tile_type: TileType::Wall,
         }
     }
 
     pub fn create_test_map(graphics: &mut Graphics) -> Map {
         let mut map = Map::new();
 
-        map.backdrop = Some(Box::new(FixedBackdrop::new("content/bkBlue.bmp", graphics)));
+        map.set_backdrop(graphics, "content/bkBlue.bmp");
 
         let num_rows = (SCREEN_HEIGHT / TILE_SIZE) as usize;
         let num_cols = (SCREEN_WIDTH / TILE_SIZE) as usize;
 
-        let blank_tile = Tile::new();
-        let blank_row: Vec<Tile> = repeat(blank_tile).take(num_cols).collect();
-        map.tiles = repeat(blank_row.clone()).take(num_rows).collect();
-        map.background_tiles = repeat(blank_row.clone()).take(num_rows).collect();
-
-        let file_path = "content/PrtCave.bmp";
-        let sprite = Rc::new(RefCell::new(Box::new(StaticSprite::new(graphics,
-                                                                     file_path,
-                                                                     TILE_SIZE as i32,
-                                                                     0,
-                                                                     TILE_SIZE,
-                                                                     TILE_SIZE)) as
-                                          Box<Sprite>));
-        let chain_top = Rc::new(RefCell::new(Box::new(StaticSprite::new(graphics,
-                                                                        file_path,
-                                                                        11 * TILE_SIZE as i32,
-                                                                        2 * TILE_SIZE as i32,
-                                                                        TILE_SIZE,
-                                                                        TILE_SIZE)) as
-                                             Box<Sprite>));
-        let chain_middle = Rc::new(RefCell::new(Box::new(StaticSprite::new(graphics,
-                                                                           file_path,
-                                                                           12 *
-                                                                           TILE_SIZE as i32,
-                                                                           2 * TILE_SIZE as i32,
-                                                                           TILE_SIZE,
-                                                                           TILE_SIZE)) as
-                                                Box<Sprite>));
-        let chain_bottom = Rc::new(RefCell::new(Box::new(StaticSprite::new(graphics,
-                                                                           file_path,
-                                                                           13 *
-                                                                           TILE_SIZE as i32,
-                                                                           2 * TILE_SIZE as i32,
-                                                                           TILE_SIZE,
-                                                                           TILE_SIZE)) as
-                                                Box<Sprite>));
-        let wall_tile = Tile::from_sprite(sprite, TileType::Wall);
+        map.num_rows = num_rows;
+        map.num_cols = num_cols;
+        let background = map.add_layer(Layer::new("background", num_rows, num_cols)
+                                           .with_draw_order(ENTITY_DRAW_ORDER - 2));
+        let main = map.add_layer(Layer::new("main", num_rows, num_cols)
+                                     .with_draw_order(ENTITY_DRAW_ORDER - 1)
+                                     .with_collision(true));
+        map.add_layer(Layer::new("foreground", num_rows, num_cols)
+                          .with_draw_order(ENTITY_DRAW_ORDER + 1));
+
+        let mut tileset = Tileset::new(graphics, "content/PrtCave.bmp")
+            .expect("Failed to load the test map tileset");
+        tileset.set_tile_type(1, TileType::Wall);
+        let wall_tile = tileset.tile(1);
+        let chain_top_tile = tileset.tile(2 * 16 + 11);
+        let chain_middle_tile = tileset.tile(2 * 16 + 12);
+        let chain_bottom_tile = tileset.tile(2 * 16 + 13);
+        let door_top_tile = tileset.tile(10);
+        let door_bottom_tile = tileset.tile(16 + 10);
+        map.tileset = Some(tileset);
 
         // floor
         for col in 0..num_cols {
-            map.tiles[num_rows - 1][col] = wall_tile.clone();
+            map.set_tile(main, num_rows - 1, col, wall_tile).unwrap();
         }
 
         // walls
         for row in 0..num_rows {
-            map.tiles[row][0] = wall_tile.clone();
-            map.tiles[row][num_cols - 1] = wall_tile.clone();
+            map.set_tile(main, row, 0, wall_tile).unwrap();
+            map.set_tile(main, row, num_cols - 1, wall_tile).unwrap();
         }
 
-        map.tiles[num_rows - 2][3] = wall_tile.clone();
-        map.tiles[num_rows - 2][5] = wall_tile.clone();
+        map.set_tile(main, num_rows - 2, 3, wall_tile).unwrap();
+        map.set_tile(main, num_rows - 2, 5, wall_tile).unwrap();
+
+        map.set_tile(main, num_rows - 3, 4, wall_tile).unwrap();
+        map.set_tile(main, num_rows - 4, 3, wall_tile).unwrap();
+        map.set_tile(main, num_rows - 5, 2, wall_tile).unwrap();
 
-        map.tiles[num_rows - 3][4] = wall_tile.clone();
-        map.tiles[num_rows - 4][3] = wall_tile.clone();
-        map.tiles[num_rows - 5][2] = wall_tile.clone();
+        map.set_tile(background, num_rows - 4, 2, chain_top_tile).unwrap();
+        map.set_tile(background, num_rows - 3, 2, chain_middle_tile).unwrap();
+        map.set_tile(background, num_rows - 2, 2, chain_bottom_tile).unwrap();
 
-        let chain_top_tile = Tile::from_sprite(chain_top, TileType::Air);
-        let chain_middle_tile = Tile::from_sprite(chain_middle, TileType::Air);
-        let chain_bottom_tile = Tile::from_sprite(chain_bottom, TileType::Air);
+        // A door into content/stages/first_cave.map
+        map.set_tile(background, num_rows - 3, 16, door_top_tile).unwrap();
+        map.set_tile(background, num_rows - 2, 16, door_bottom_tile).unwrap();
+        map.add_warp(Warp::new(WarpTrigger::Door,
+                               num_rows as i32 - 2,
+                               16,
+                               1,
+                               1,
+                               WarpDestination::new("first_cave", 13, 2)));
 
-        map.background_tiles[num_rows - 4][2] = chain_top_tile.clone();
-        map.background_tiles[num_rows - 3][2] = chain_middle_tile.clone();
-        map.background_tiles[num_rows - 2][2] = chain_bottom_tile.clone();
+        map.add_entity(EntityPlacement::new("save_point", num_rows - 2, 8, 0));
+        map.add_entity(EntityPlacement::new("refill_station", num_rows - 2, 11, 0));
 
         map
     }
 
+    pub fn load(graphics: &mut Graphics, file_path: &str) -> Result<Map, String> {
+        let mut text = String::new();
+        File::open(file_path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|e| format!("Failed to read map {}: {}", file_path, e))?;
+        format::read(graphics, &text)
+            .map_err(|e| format!("Failed to load map {}: {}", file_path, e))
+    }
+
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        let text = format::write(self)
+            .map_err(|e| format!("Failed to save map {}: {}", file_path, e))?;
+        File::create(file_path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| format!("Failed to save map {}: {}", file_path, e))
+    }
+
+    pub fn set_backdrop(&mut self, graphics: &mut Graphics, file_path: &str) {
+        self.backdrop = Some(Box::new(FixedBackdrop::new(file_path, graphics)));
+        self.backdrop_path = Some(String::from(file_path));
+    }
+
+    pub fn entities(&self) -> &[EntityPlacement] {
+        &self.entities
+    }
+
+    pub fn add_entity(&mut self, entity: EntityPlacement) {
+        self.entities.push(entity);
+    }
+
+    pub fn triggers(&self) -> &[TriggerPlacement] {
+        &self.triggers
+    }
+
+    pub fn add_trigger(&mut self, trigger: TriggerPlacement) {
+        self.triggers.push(trigger);
+    }
+
+    pub fn warps(&self) -> &[Warp] {
+        &self.warps
+    }
+
+    pub fn add_warp(&mut self, warp: Warp) {
+        self.warps.push(warp);
+    }
+
+    // First warp of the given kind whose area overlaps `rect`
+    pub fn find_warp(&self, rect: &Rect, trigger: WarpTrigger) -> Option<&Warp> {
+        self.warps
+            .iter()
+            .find(|warp| warp.trigger() == trigger && warp.area().has_intersection(*rect))
+    }
+
+    pub fn tileset(&self) -> Option<&Tileset> {
+        self.tileset.as_ref()
+    }
+
+    pub fn set_tileset(&mut self, tileset: Tileset) {
+        self.tileset = Some(tileset);
+        self.tile_cache.invalidate_all();
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    // Adds a layer the size of the map and returns its index for the tile functions
+    pub fn add_layer(&mut self, layer: Layer) -> usize {
+        assert!(layer.num_rows() == self.num_rows && layer.num_cols() == self.num_cols,
+                "Layer {} does not match the {}x{} map",
+                layer.name(),
+                self.num_rows,
+                self.num_cols);
+        self.layers.push(layer);
+
+        let layers = &self.layers;
+        self.draw_sequence = (0..layers.len()).collect();
+        self.draw_sequence.sort_by_key(|&index| layers[index].draw_order());
+
+        self.layers.len() - 1
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name() == name)
+    }
+
+    pub fn set_camera(&mut self, x: i32, y: i32) {
+        self.camera = Point::new(x, y);
+    }
+
+    pub fn tile(&self, layer: usize, row: usize, col: usize) -> Option<&Tile> {
+        self.layers.get(layer).and_then(|layer| layer.tile(row, col))
+    }
+
+    // Replaces the tile at (row, col) and returns the one that was there before. Setting the tile
+    // that's already there isn't a change, so it's neither recorded nor redrawn.
+    pub fn set_tile(&mut self,
+                    layer: usize,
+                    row: usize,
+                    col: usize,
+                    tile: Tile)
+                    -> Result<Tile, String> {
+        let (num_rows, num_cols) = (self.num_rows, self.num_cols);
+        let old_tile = match self.layers
+                  .get_mut(layer)
+                  .and_then(|layer| layer.tile_mut(row, col)) {
+            Some(slot) => {
+                if *slot == tile {
+                    return Ok(tile);
+                }
+                ::std::mem::replace(slot, tile)
+            }
+            None => {
+                return Err(format!("Tile ({}, {}) on layer {} is outside of the {}x{} map",
+                                   row,
+                                   col,
+                                   layer,
+                                   num_rows,
+                                   num_cols))
+            }
+        };
+        self.changed_tiles.push(TileChange::new(layer, row, col));
+        self.tile_cache.invalidate(layer, row, col);
+        Ok(old_tile)
+    }
+
+    // Tiles replaced through `set_tile` since the last call to `begin_new_frame`
+    pub fn changed_tiles(&self) -> &[TileChange] {
+        &self.changed_tiles
+    }
+
+    pub fn begin_new_frame(&mut self) {
+        self.changed_tiles.clear();
+    }
+
+    // Tile type reported by collision queries for anything outside of the map. `Air` lets
+    // entities leave through the edges, e.g. into an edge warp.
+    pub fn set_boundary_tile_type(&mut self, tile_type: TileType) {
+        self.boundary_tile_type = tile_type;
+    }
+
+    pub fn boundary_tile_type(&self) -> TileType {
+        self.boundary_tile_type.clone()
+    }
+
     pub fn get_colliding_tiles(&self, rect: &Rect) -> Vec<CollisionTile> {
         let tile_size = TILE_SIZE as i32;
-        let first_row = rect.top() / tile_size;
-        let last_row = rect.bottom() / tile_size;
-        let first_col = rect.left() / tile_size;
-        let last_col = rect.right() / tile_size;
+        let first_row = floor_div(rect.top(), tile_size);
+        let last_row = floor_div(rect.bottom(), tile_size);
+        let first_col = floor_div(rect.left(), tile_size);
+        let last_col = floor_div(rect.right(), tile_size);
 
         let total_tiles = ((last_row - first_row + 1) * (last_col - first_col + 1)) as usize;
         let mut collision_tiles = Vec::with_capacity(total_tiles);
         for row in first_row..(last_row + 1) {
             for col in first_col..(last_col + 1) {
-                collision_tiles.push(CollisionTile::new(row,
-                                                        col,
-                                                        self.tiles[row as usize][col as usize]
-                                                            .tile_type()));
+                let tile_type = self.collision_tile_type(row, col);
+                collision_tiles.push(CollisionTile::new(row, col, tile_type));
             }
         }
 
         collision_tiles
     }
 
-    pub fn update(&mut self, elapsed_time: Duration) {
-        for row in self.tiles.as_mut_slice() {
-            for col in row.as_mut_slice() {
-                if let Some(sprite) = col.sprite() {
-                    sprite.borrow_mut().update(elapsed_time);
-                }
+    fn collision_tile_type(&self, row: i32, col: i32) -> TileType {
+        if row < 0 || col < 0 || row as usize >= self.num_rows || col as usize >= self.num_cols {
+            return self.boundary_tile_type();
+        }
+        // A wall on any layer wins over a spike on another
+        let mut tile_type = TileType::Air;
+        let tiles = self.layers
+            .iter()
+            .filter(|layer| layer.collides())
+            .filter_map(|layer| layer.tile(row as usize, col as usize));
+        for tile in tiles {
+            match tile.tile_type() {
+                TileType::Wall => return TileType::Wall,
+                TileType::Spike => tile_type = TileType::Spike,
+                TileType::Air => {}
             }
         }
+        tile_type
     }
 
-    pub fn draw(&mut self, graphics: &mut Graphics) {
-        for row in 0..self.tiles.len() {
-            for col in 0..self.tiles[row].len() {
-                if let Some(sprite) = self.tiles[row][col].sprite() {
-                    let x = col as u32 * TILE_SIZE;
-                    let y = row as u32 * TILE_SIZE;
-                    sprite.borrow_mut().draw(graphics, x as i32, y as i32);
-                }
-            }
-        }
+    pub fn update(&mut self, elapsed_time: Duration) {
+        // Animated tiles on every layer are driven from this one clock by the tileset
+        self.animation_clock = self.animation_clock + elapsed_time;
     }
 
+    // Draws the backdrop and every layer that sits behind entities
     pub fn draw_background(&mut self, graphics: &mut Graphics) {
         if let Some(ref mut backdrop) = self.backdrop {
             backdrop.draw(graphics);
         }
 
-        for row in 0..self.background_tiles.len() {
-            for col in 0..self.background_tiles.len() {
-                if let Some(sprite) = self.background_tiles[row][col].sprite() {
-                    let x = col as u32 * TILE_SIZE;
-                    let y = row as u32 * TILE_SIZE;
-                    sprite.borrow_mut().draw(graphics, x as i32, y as i32);
-                }
+        for position in 0..self.draw_sequence.len() {
+            let index = self.draw_sequence[position];
+            if !self.layers[index].is_above_entities() {
+                self.draw_layer(index, graphics);
             }
         }
     }
+
+    // Draws every layer that sits in front of entities
+    pub fn draw_foreground(&mut self, graphics: &mut Graphics) {
+        for position in 0..self.draw_sequence.len() {
+            let index = self.draw_sequence[position];
+            if self.layers[index].is_above_entities() {
+                self.draw_layer(index, graphics);
+            }
+        }
+    }
+
+    fn draw_layer(&mut self, index: usize, graphics: &mut Graphics) {
+        let tileset = match self.tileset {
+            Some(ref tileset) => tileset,
+            None => return,
+        };
+        let layer = &self.layers[index];
+        let offset_x = (self.camera.x() as f32 * layer.parallax()).round() as i32;
+        let offset_y = (self.camera.y() as f32 * layer.parallax()).round() as i32;
+        self.tile_cache.draw_layer(graphics, tileset, index, layer, offset_x, offset_y);
+
+        // Animated tiles are left out of the cache and drawn over it every frame. Only the tiles
+        // on screen are looked at.
+        let (screen_width, screen_height) = graphics.screen_size();
+        let tile_size = TILE_SIZE as i32;
+        let first_row = floor_div(offset_y, tile_size).max(0) as usize;
+        let first_col = floor_div(offset_x, tile_size).max(0) as usize;
+        let last_row = floor_div(offset_y + screen_height as i32 - 1, tile_size) + 1;
+        let last_col = floor_div(offset_x + screen_width as i32 - 1, tile_size) + 1;
+        let last_row = (last_row.max(0) as usize).min(layer.num_rows());
+        let last_col = (last_col.max(0) as usize).min(layer.num_cols());
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                let tile_index = match layer.tile(row, col).and_then(|tile| tile.index()) {
+                    Some(tile_index) if tileset.is_animated(tile_index) => tile_index,
+                    _ => continue,
+                };
+                let x = (col as u32 * TILE_SIZE) as i32 - offset_x;
+                let y = (row as u32 * TILE_SIZE) as i32 - offset_y;
+                tileset.draw_tile(graphics, tile_index, self.animation_clock, row, col, x, y);
+            }
+        }
+    }
+}
+
+// Integer division rounding towards negative infinity, so pixel -1 lands in tile -1
+fn floor_div(a: i32, b: i32) -> i32 {
+    let quotient = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_the_same_tile_is_not_a_change() {
+        let mut map = Map::from_ascii("#.", &AsciiLegend::new()).unwrap();
+        let wall = Tile::with_type(TileType::Wall);
+        assert_eq!(map.set_tile(0, 0, 0, wall), Ok(wall));
+        assert!(map.changed_tiles().is_empty());
+
+        map.set_tile(0, 0, 1, wall).unwrap();
+        assert_eq!(map.changed_tiles().len(), 1);
+    }
+
+    #[test]
+    fn spikes_collide_unless_another_layer_has_a_wall() {
+        let legend = AsciiLegend::new().with('^', TileType::Spike, None);
+        let mut map = Map::from_ascii("^^.", &legend).unwrap();
+        let mut front = Layer::new("front", 1, 3).with_collision(true);
+        *front.tile_mut(0, 1).unwrap() = Tile::with_type(TileType::Wall);
+        map.add_layer(front);
+
+        let tile_types: Vec<TileType> = map.get_colliding_tiles(&Rect::new(0, 0, 90, 10))
+            .iter()
+            .map(|tile| tile.tile_type())
+            .collect();
+        assert_eq!(tile_types, [TileType::Spike, TileType::Wall, TileType::Air]);
+    }
 }
@@ -0,0 +1,452 @@
+use map::Map;
+use map::tile::TileType;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+const JUMP_PENALTY: u32 = 5;
+// Upper bound on nodes expanded per search so a hopeless path can't eat the frame
+const MAX_EXPANDED_NODES: usize = 4096;
+const NO_PARENT: usize = ::std::usize::MAX;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathMode {
+    // Moves in 8 directions through any open tile
+    Flying,
+    // Stays on the ground, jumping up to `jump_height` tiles and `jump_distance` tiles across,
+    // and dropping down ledges up to `max_drop` tiles high
+    Walking {
+        jump_height: usize,
+        jump_distance: usize,
+        max_drop: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathAction {
+    Start,
+    Walk,
+    Jump,
+    Fall,
+    Fly,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PathStep {
+    row: usize,
+    col: usize,
+    action: PathAction,
+}
+
+impl PathStep {
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    // How the entity gets into this tile from the previous step
+    pub fn action(&self) -> PathAction {
+        self.action
+    }
+}
+
+#[derive(Eq, PartialEq)]
+struct OpenNode {
+    estimate: u32,
+    index: usize,
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &OpenNode) -> Ordering {
+        // BinaryHeap is a max heap, so the cheapest estimate has to compare greatest
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &OpenNode) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A* over the map's tiles. Keep one per enemy type (or one shared) and reuse it: the search
+// buffers are flat arrays sized to the map and are reset with a generation counter instead of
+// being cleared for every search.
+pub struct Pathfinder {
+    num_rows: usize,
+    num_cols: usize,
+    generation: u32,
+    visited: Vec<u32>,
+    costs: Vec<u32>,
+    parents: Vec<usize>,
+    actions: Vec<PathAction>,
+    open: BinaryHeap<OpenNode>,
+    neighbours: Vec<(usize, PathAction, u32)>,
+}
+
+impl Pathfinder {
+    pub fn new() -> Pathfinder {
+        Pathfinder {
+            num_rows: 0,
+            num_cols: 0,
+            generation: 0,
+            visited: vec![],
+            costs: vec![],
+            parents: vec![],
+            actions: vec![],
+            open: BinaryHeap::new(),
+            neighbours: vec![],
+        }
+    }
+
+    // Fills `path` with the steps from `start` to `goal` as (row, col) tiles, including both
+    // ends. Returns false and leaves `path` empty when there is no path.
+    pub fn find_path(&mut self,
+                     map: &Map,
+                     start: (usize, usize),
+                     goal: (usize, usize),
+                     mode: PathMode,
+                     path: &mut Vec<PathStep>)
+                     -> bool {
+        path.clear();
+        self.begin_search(map);
+
+        let (start, goal) = match mode {
+            PathMode::Flying => (start, goal),
+            PathMode::Walking { .. } => {
+                match (ground_below(map, start), ground_below(map, goal)) {
+                    (Some(start), Some(goal)) => (start, goal),
+                    _ => return false,
+                }
+            }
+        };
+        if !is_open(map, start.0 as i32, start.1 as i32) ||
+           !is_open(map, goal.0 as i32, goal.1 as i32) {
+            return false;
+        }
+
+        let start_index = self.index(start);
+        let goal_index = self.index(goal);
+        self.visit(start_index, 0, NO_PARENT, PathAction::Start);
+        self.open
+            .push(OpenNode {
+                      estimate: heuristic(start, goal, mode),
+                      index: start_index,
+                  });
+
+        let mut expanded = 0;
+        while let Some(OpenNode { estimate, index }) = self.open.pop() {
+            if index == goal_index {
+                self.build_path(goal_index, path);
+                return true;
+            }
+            let position = (index / self.num_cols, index % self.num_cols);
+            let cost = self.costs[index];
+            if estimate > cost + heuristic(position, goal, mode) {
+                // Stale entry, the node was reached more cheaply after this was queued
+                continue;
+            }
+            expanded += 1;
+            if expanded > MAX_EXPANDED_NODES {
+                break;
+            }
+
+            self.neighbours.clear();
+            match mode {
+                PathMode::Flying => flying_neighbours(map, position, &mut self.neighbours),
+                PathMode::Walking {
+                    jump_height,
+                    jump_distance,
+                    max_drop,
+                } => {
+                    walking_neighbours(map,
+                                       position,
+                                       jump_height,
+                                       jump_distance,
+                                       max_drop,
+                                       &mut self.neighbours)
+                }
+            }
+
+            for i in 0..self.neighbours.len() {
+                let (neighbour_index, action, step_cost) = self.neighbours[i];
+                let new_cost = cost + step_cost;
+                if self.visited[neighbour_index] == self.generation &&
+                   self.costs[neighbour_index] <= new_cost {
+                    continue;
+                }
+                self.visit(neighbour_index, new_cost, index, action);
+                let neighbour_position = (neighbour_index / self.num_cols,
+                                          neighbour_index % self.num_cols);
+                self.open
+                    .push(OpenNode {
+                              estimate: new_cost + heuristic(neighbour_position, goal, mode),
+                              index: neighbour_index,
+                          });
+            }
+        }
+
+        false
+    }
+
+    fn begin_search(&mut self, map: &Map) {
+        if self.num_rows != map.num_rows() || self.num_cols != map.num_cols() {
+            self.num_rows = map.num_rows();
+            self.num_cols = map.num_cols();
+            let size = self.num_rows * self.num_cols;
+            self.visited = vec![0; size];
+            self.costs = vec![0; size];
+            self.parents = vec![NO_PARENT; size];
+            self.actions = vec![PathAction::Start; size];
+            self.generation = 0;
+        }
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            // Wrapped around, stamps from 4 billion searches ago would look current
+            for stamp in self.visited.iter_mut() {
+                *stamp = 0;
+            }
+            self.generation = 1;
+        }
+        self.open.clear();
+    }
+
+    fn index(&self, (row, col): (usize, usize)) -> usize {
+        row * self.num_cols + col
+    }
+
+    fn visit(&mut self, index: usize, cost: u32, parent: usize, action: PathAction) {
+        self.visited[index] = self.generation;
+        self.costs[index] = cost;
+        self.parents[index] = parent;
+        self.actions[index] = action;
+    }
+
+    fn build_path(&self, goal_index: usize, path: &mut Vec<PathStep>) {
+        let mut index = goal_index;
+        while index != NO_PARENT {
+            path.push(PathStep {
+                          row: index / self.num_cols,
+                          col: index % self.num_cols,
+                          action: self.actions[index],
+                      });
+            index = self.parents[index];
+        }
+        path.reverse();
+    }
+}
+
+fn is_open(map: &Map, row: i32, col: i32) -> bool {
+    row >= 0 && col >= 0 && (row as usize) < map.num_rows() && (col as usize) < map.num_cols() &&
+    map.collision_tile_type(row, col) != TileType::Wall
+}
+
+fn is_standable(map: &Map, row: i32, col: i32) -> bool {
+    is_open(map, row, col) && map.collision_tile_type(row + 1, col) == TileType::Wall
+}
+
+fn ground_below(map: &Map, (row, col): (usize, usize)) -> Option<(usize, usize)> {
+    (row..map.num_rows())
+        .take_while(|&row| is_open(map, row as i32, col as i32))
+        .find(|&row| is_standable(map, row as i32, col as i32))
+        .map(|row| (row, col))
+}
+
+fn heuristic((row, col): (usize, usize),
+             (goal_row, goal_col): (usize, usize),
+             mode: PathMode)
+             -> u32 {
+    let d_row = (row as i32 - goal_row as i32).abs() as u32;
+    let d_col = (col as i32 - goal_col as i32).abs() as u32;
+    match mode {
+        PathMode::Flying => {
+            let (low, high) = if d_row < d_col { (d_row, d_col) } else { (d_col, d_row) };
+            DIAGONAL_COST * low + STRAIGHT_COST * (high - low)
+        }
+        // Every column costs at least a straight step, and only jumps climb, at no less than two
+        // straight steps a row. Going down can be nearly free, so it isn't counted.
+        PathMode::Walking { .. } => {
+            let rise = (row as i32 - goal_row as i32).max(0) as u32;
+            STRAIGHT_COST * d_col + 2 * STRAIGHT_COST * rise
+        }
+    }
+}
+
+fn flying_neighbours(map: &Map,
+                     (row, col): (usize, usize),
+                     neighbours: &mut Vec<(usize, PathAction, u32)>) {
+    let (row, col) = (row as i32, col as i32);
+    for d_row in -1..2 {
+        for d_col in -1..2 {
+            if d_row == 0 && d_col == 0 || !is_open(map, row + d_row, col + d_col) {
+                continue;
+            }
+            let cost = if d_row != 0 && d_col != 0 {
+                // No squeezing diagonally between two walls
+                if !is_open(map, row + d_row, col) || !is_open(map, row, col + d_col) {
+                    continue;
+                }
+                DIAGONAL_COST
+            } else {
+                STRAIGHT_COST
+            };
+            let index = (row + d_row) as usize * map.num_cols() + (col + d_col) as usize;
+            neighbours.push((index, PathAction::Fly, cost));
+        }
+    }
+}
+
+fn walking_neighbours(map: &Map,
+                      (row, col): (usize, usize),
+                      jump_height: usize,
+                      jump_distance: usize,
+                      max_drop: usize,
+                      neighbours: &mut Vec<(usize, PathAction, u32)>) {
+    let (row, col) = (row as i32, col as i32);
+    let index = |row: i32, col: i32| row as usize * map.num_cols() + col as usize;
+
+    for &d_col in [-1, 1].iter() {
+        let next_col = col + d_col;
+        if !is_open(map, row, next_col) {
+            continue;
+        }
+        if is_standable(map, row, next_col) {
+            neighbours.push((index(row, next_col), PathAction::Walk, STRAIGHT_COST));
+        } else if let Some(landing) = landing_row(map, row, next_col, max_drop) {
+            let drop = (landing - row) as u32;
+            neighbours.push((index(landing, next_col),
+                             PathAction::Fall,
+                             STRAIGHT_COST * (1 + drop)));
+        }
+    }
+
+    // Jumps rise straight up to an apex, travel across and then fall onto ground
+    for height in 1..(jump_height as i32 + 1) {
+        let apex = row - height;
+        if !is_open(map, apex, col) {
+            break;
+        }
+        for &direction in [-1, 1].iter() {
+            for distance in 0..(jump_distance as i32 + 1) {
+                let target_col = col + direction * distance;
+                if !is_open(map, apex, target_col) {
+                    break;
+                }
+                if distance == 0 && direction > 0 {
+                    // Straight up was already handled going left
+                    continue;
+                }
+                let max_fall = max_drop + height as usize;
+                if let Some(landing) = landing_row(map, apex, target_col, max_fall) {
+                    if landing == row && distance <= 1 {
+                        // Same as walking
+                        continue;
+                    }
+                    // The entity is still crossing the last column as it starts to fall, until
+                    // it's back down to where it jumped from or landed
+                    let last_col = target_col - direction;
+                    if distance > 0 &&
+                       !(apex..row.min(landing)).all(|fall_row| is_open(map, fall_row, last_col)) {
+                        continue;
+                    }
+                    let cost = STRAIGHT_COST * (distance as u32 + 2 * height as u32) +
+                               JUMP_PENALTY;
+                    neighbours.push((index(landing, target_col), PathAction::Jump, cost));
+                }
+            }
+        }
+    }
+}
+
+// First standable row at or below `row` in `col`, at most `max_drop` tiles further down
+fn landing_row(map: &Map, row: i32, col: i32, max_drop: usize) -> Option<i32> {
+    (row..(row + max_drop as i32 + 1))
+        .take_while(|&row| is_open(map, row, col))
+        .find(|&row| is_standable(map, row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::AsciiLegend;
+
+    const WALKER: PathMode = PathMode::Walking {
+        jump_height: 2,
+        jump_distance: 1,
+        max_drop: 3,
+    };
+
+    fn map(art: &str) -> Map {
+        Map::from_ascii(art, &AsciiLegend::new()).unwrap()
+    }
+
+    fn steps(path: &[PathStep]) -> Vec<(usize, usize, PathAction)> {
+        path.iter().map(|step| (step.row(), step.col(), step.action())).collect()
+    }
+
+    #[test]
+    fn flying_never_cuts_wall_corners() {
+        let open = map("....
+                        .#..
+                        ....");
+        let mut path = vec![];
+        assert!(Pathfinder::new().find_path(&open, (0, 0), (2, 2), PathMode::Flying, &mut path));
+        for pair in path.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            assert!(is_open(&open, from.row() as i32, to.col() as i32));
+            assert!(is_open(&open, to.row() as i32, from.col() as i32));
+        }
+
+        let boxed_in = map(".#.
+                            #..
+                            ...");
+        assert!(!Pathfinder::new().find_path(&boxed_in,
+                                             (0, 0),
+                                             (2, 2),
+                                             PathMode::Flying,
+                                             &mut path));
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn walking_drops_off_ledges() {
+        let ledge = map("......
+                         ###...
+                         ###...
+                         ######");
+        let mut path = vec![];
+        assert!(Pathfinder::new().find_path(&ledge, (0, 0), (2, 4), WALKER, &mut path));
+        assert_eq!(steps(&path),
+                   vec![(0, 0, PathAction::Start),
+                        (0, 1, PathAction::Walk),
+                        (0, 2, PathAction::Walk),
+                        (2, 3, PathAction::Fall),
+                        (2, 4, PathAction::Walk)]);
+    }
+
+    #[test]
+    fn walking_jumps_no_higher_than_jump_height() {
+        let step = map(".....
+                        ...##
+                        ...##
+                        #####");
+        let mut path = vec![];
+        assert!(Pathfinder::new().find_path(&step, (2, 0), (0, 3), WALKER, &mut path));
+        assert_eq!(path.last().map(|step| (step.row(), step.col(), step.action())),
+                   Some((0, 3, PathAction::Jump)));
+
+        let low_jumper = PathMode::Walking {
+            jump_height: 1,
+            jump_distance: 1,
+            max_drop: 3,
+        };
+        assert!(!Pathfinder::new().find_path(&step, (2, 0), (0, 3), low_jumper, &mut path));
+    }
+}
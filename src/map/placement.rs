@@ -0,0 +1,94 @@
+// Positions are in tiles. `event` is the script event run for the placement, 0 for none.
+// `flag` hides an entity once that game flag is set, 0 for none.
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntityPlacement {
+    kind: String,
+    row: usize,
+    col: usize,
+    event: u16,
+    flag: u16,
+}
+
+impl EntityPlacement {
+    pub fn new(kind: &str, row: usize, col: usize, event: u16) -> EntityPlacement {
+        EntityPlacement {
+            kind: String::from(kind),
+            row,
+            col,
+            event,
+            flag: 0,
+        }
+    }
+
+    pub fn with_flag(mut self, flag: u16) -> EntityPlacement {
+        self.flag = flag;
+        self
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    pub fn event(&self) -> u16 {
+        self.event
+    }
+
+    pub fn flag(&self) -> u16 {
+        self.flag
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TriggerPlacement {
+    row: usize,
+    col: usize,
+    num_rows: usize,
+    num_cols: usize,
+    event: u16,
+}
+
+impl TriggerPlacement {
+    pub fn new(row: usize,
+               col: usize,
+               num_rows: usize,
+               num_cols: usize,
+               event: u16)
+               -> TriggerPlacement {
+        TriggerPlacement {
+            row,
+            col,
+            num_rows,
+            num_cols,
+            event,
+        }
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    pub fn event(&self) -> u16 {
+        self.event
+    }
+}
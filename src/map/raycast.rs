@@ -0,0 +1,180 @@
+use game::TILE_SIZE;
+use map::Map;
+use map::tile::TileType;
+use std::f32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    x: f32,
+    y: f32,
+    row: i32,
+    col: i32,
+    normal: (i32, i32),
+    distance: f32,
+}
+
+impl RaycastHit {
+    // Point in pixels where the ray enters the wall
+    pub fn point(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    pub fn row(&self) -> i32 {
+        self.row
+    }
+
+    pub fn col(&self) -> i32 {
+        self.col
+    }
+
+    // Unit vector pointing out of the face that was hit, (0, 0) when the ray starts in a wall
+    pub fn normal(&self) -> (i32, i32) {
+        self.normal
+    }
+
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+}
+
+impl Map {
+    // Walks the tile grid from `from` to `to` (in pixels) and returns the first wall crossed.
+    pub fn raycast(&self, from: (f32, f32), to: (f32, f32)) -> Option<RaycastHit> {
+        let tile_size = TILE_SIZE as f32;
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+
+        let mut row = (from.1 / tile_size).floor() as i32;
+        let mut col = (from.0 / tile_size).floor() as i32;
+        if self.collision_tile_type(row, col) == TileType::Wall {
+            return Some(RaycastHit {
+                            x: from.0,
+                            y: from.1,
+                            row,
+                            col,
+                            normal: (0, 0),
+                            distance: 0.0,
+                        });
+        }
+        if length == 0.0 {
+            return None;
+        }
+
+        let (dir_x, dir_y) = (dx / length, dy / length);
+        let (step_x, mut t_max_x, t_delta_x) = axis_steps(from.0, dir_x, col, tile_size);
+        let (step_y, mut t_max_y, t_delta_y) = axis_steps(from.1, dir_y, row, tile_size);
+
+        loop {
+            let (distance, normal) = if t_max_x < t_max_y {
+                let distance = t_max_x;
+                col += step_x;
+                t_max_x += t_delta_x;
+                (distance, (-step_x, 0))
+            } else {
+                let distance = t_max_y;
+                row += step_y;
+                t_max_y += t_delta_y;
+                (distance, (0, -step_y))
+            };
+
+            if distance > length {
+                return None;
+            }
+            if self.collision_tile_type(row, col) == TileType::Wall {
+                return Some(RaycastHit {
+                                x: from.0 + dir_x * distance,
+                                y: from.1 + dir_y * distance,
+                                row,
+                                col,
+                                normal,
+                                distance,
+                            });
+            }
+        }
+    }
+
+    pub fn has_line_of_sight(&self, from: (f32, f32), to: (f32, f32)) -> bool {
+        self.raycast(from, to).is_none()
+    }
+}
+
+// Step direction, distance along the ray to the first tile boundary, and distance between
+// boundaries for one axis.
+fn axis_steps(origin: f32, direction: f32, tile: i32, tile_size: f32) -> (i32, f32, f32) {
+    if direction > 0.0 {
+        (1, ((tile + 1) as f32 * tile_size - origin) / direction, tile_size / direction)
+    } else if direction < 0.0 {
+        (-1, (tile as f32 * tile_size - origin) / direction, tile_size / -direction)
+    } else {
+        (0, f32::INFINITY, f32::INFINITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::AsciiLegend;
+
+    fn test_map() -> Map {
+        Map::from_ascii("#....
+                         .....
+                         ...#.",
+                        &AsciiLegend::new())
+                .unwrap()
+    }
+
+    #[test]
+    fn hits_the_face_facing_the_ray_on_each_axis() {
+        let map = test_map();
+
+        let hit = map.raycast((16.0, 80.0), (150.0, 80.0)).unwrap();
+        assert_eq!(hit.point(), (96.0, 80.0));
+        assert_eq!((hit.row(), hit.col()), (2, 3));
+        assert_eq!(hit.normal(), (-1, 0));
+        assert_eq!(hit.distance(), 80.0);
+
+        let hit = map.raycast((144.0, 80.0), (0.0, 80.0)).unwrap();
+        assert_eq!(hit.point(), (128.0, 80.0));
+        assert_eq!(hit.normal(), (1, 0));
+
+        let hit = map.raycast((112.0, 16.0), (112.0, 150.0)).unwrap();
+        assert_eq!(hit.point(), (112.0, 64.0));
+        assert_eq!((hit.row(), hit.col()), (2, 3));
+        assert_eq!(hit.normal(), (0, -1));
+
+        let hit = map.raycast((16.0, 80.0), (16.0, -10.0)).unwrap();
+        assert_eq!(hit.point(), (16.0, 32.0));
+        assert_eq!((hit.row(), hit.col()), (0, 0));
+        assert_eq!(hit.normal(), (0, 1));
+    }
+
+    #[test]
+    fn stops_short_of_walls_past_the_end() {
+        let map = test_map();
+        assert_eq!(map.raycast((16.0, 48.0), (150.0, 48.0)), None);
+        assert_eq!(map.raycast((16.0, 80.0), (90.0, 80.0)), None);
+        assert!(map.has_line_of_sight((16.0, 80.0), (90.0, 80.0)));
+    }
+
+    #[test]
+    fn starting_inside_a_wall_hits_right_away() {
+        let hit = test_map().raycast((100.0, 70.0), (0.0, 70.0)).unwrap();
+        assert_eq!(hit.point(), (100.0, 70.0));
+        assert_eq!((hit.row(), hit.col()), (2, 3));
+        assert_eq!(hit.normal(), (0, 0));
+        assert_eq!(hit.distance(), 0.0);
+    }
+
+    #[test]
+    fn leaving_the_map_hits_the_boundary_type() {
+        let mut map = test_map();
+        let hit = map.raycast((80.0, 16.0), (80.0, -50.0)).unwrap();
+        assert_eq!(hit.point(), (80.0, 0.0));
+        assert_eq!((hit.row(), hit.col()), (-1, 2));
+        assert_eq!(hit.normal(), (0, 1));
+
+        map.set_boundary_tile_type(TileType::Air);
+        assert_eq!(map.raycast((80.0, 16.0), (80.0, -50.0)), None);
+        assert!(map.has_line_of_sight((80.0, 16.0), (80.0, -50.0)));
+    }
+}
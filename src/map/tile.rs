@@ -1,39 +1,47 @@
-use sprite::Sprite;
 use std::clone::Clone;
-use std::rc::Rc;
-use std::cell::RefCell;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum TileType {
     Air,
     Wall,
+    // Hurts the player on touch
+    Spike,
 }
 
-type TileSprite = Rc<RefCell<Box<Sprite>>>;
+// Index of a tile graphic on the map's tileset
+pub type TileIndex = u16;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Tile {
     tile_type: TileType,
-    sprite: Option<TileSprite>,
+    index: Option<TileIndex>,
 }
 
 impl Tile {
     pub fn new() -> Tile {
         Tile {
             tile_type: TileType::Air,
-            sprite: None,
+            index: None,
         }
     }
 
-    pub fn from_sprite(sprite: TileSprite, tile_type: TileType) -> Tile {
+    // A tile without a graphic, e.g. an invisible wall
+    pub fn with_type(tile_type: TileType) -> Tile {
         Tile {
             tile_type,
-            sprite: Some(sprite.clone()),
+            index: None,
         }
     }
 
-    pub fn sprite(&self) -> Option<TileSprite> {
-        self.sprite.clone()
+    pub fn from_index(index: TileIndex, tile_type: TileType) -> Tile {
+        Tile {
+            tile_type,
+            index: Some(index),
+        }
+    }
+
+    pub fn index(&self) -> Option<TileIndex> {
+        self.index
     }
 
     pub fn tile_type(&self) -> TileType {
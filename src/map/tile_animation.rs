@@ -0,0 +1,53 @@
+use map::tile::TileIndex;
+use time::Duration;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AnimationPhase {
+    // Every tile using the animation shows the same frame
+    Synchronized,
+    // Each placed tile is offset by its position so neighbours don't move in lockstep
+    PerInstance,
+}
+
+#[derive(Clone, Debug)]
+pub struct TileAnimation {
+    frames: Vec<TileIndex>,
+    fps: u32,
+    frame_time: Duration,
+    phase: AnimationPhase,
+}
+
+impl TileAnimation {
+    pub fn new(frames: Vec<TileIndex>, fps: u32, phase: AnimationPhase) -> TileAnimation {
+        assert!(!frames.is_empty(), "Tile animation needs at least one frame");
+        TileAnimation {
+            frames,
+            fps,
+            // Past 1000 fps a frame would round down to 0 ms
+            frame_time: Duration::milliseconds((1000 / fps.max(1)).max(1) as i64),
+            phase,
+        }
+    }
+
+    pub fn frames(&self) -> &[TileIndex] {
+        &self.frames
+    }
+
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    pub fn phase(&self) -> AnimationPhase {
+        self.phase
+    }
+
+    pub fn frame_index(&self, clock: Duration, row: usize, col: usize) -> TileIndex {
+        let elapsed_frames = (clock.num_milliseconds() /
+                              self.frame_time.num_milliseconds()) as usize;
+        let offset = match self.phase {
+            AnimationPhase::Synchronized => 0,
+            AnimationPhase::PerInstance => row * 3 + col,
+        };
+        self.frames[(elapsed_frames + offset) % self.frames.len()]
+    }
+}
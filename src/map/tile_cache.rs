@@ -0,0 +1,233 @@
+use game::TILE_SIZE;
+use graphics::Graphics;
+use map::layer::Layer;
+use map::tileset::Tileset;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::Texture;
+use sdl2::surface::Surface;
+
+// Tiles per side of a cached chunk
+const CHUNK_SIZE: usize = 16;
+const CHUNK_PIXELS: u32 = CHUNK_SIZE as u32 * TILE_SIZE;
+
+struct Chunk {
+    texture: Option<Texture<'static>>,
+    dirty: bool,
+}
+
+struct CachedLayer {
+    chunk_cols: usize,
+    chunks: Vec<Chunk>,
+}
+
+// Static tiles of each layer pre-rendered into offscreen chunk textures. Chunks are only
+// re-rendered after one of their tiles changes; animated tiles are left out and drawn over the
+// chunk.
+pub struct TileCache {
+    layers: Vec<CachedLayer>,
+    // Revision of the tileset the chunks were rendered with
+    tileset_revision: Option<u32>,
+    // Chunks are rendered here before being uploaded to their texture
+    scratch: Option<Surface<'static>>,
+}
+
+impl TileCache {
+    pub fn new() -> TileCache {
+        TileCache {
+            layers: vec![],
+            tileset_revision: None,
+            scratch: None,
+        }
+    }
+
+    pub fn invalidate(&mut self, layer: usize, row: usize, col: usize) {
+        if let Some(cached_layer) = self.layers.get_mut(layer) {
+            let index = (row / CHUNK_SIZE) * cached_layer.chunk_cols + col / CHUNK_SIZE;
+            if let Some(chunk) = cached_layer.chunks.get_mut(index) {
+                chunk.dirty = true;
+            }
+        }
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.layers.clear();
+    }
+
+    pub fn draw_layer(&mut self,
+                      graphics: &mut Graphics,
+                      tileset: &Tileset,
+                      index: usize,
+                      layer: &Layer,
+                      offset_x: i32,
+                      offset_y: i32) {
+        // Tiles that became animated have to leave the cache, and the other way around
+        if self.tileset_revision != Some(tileset.revision()) {
+            self.invalidate_all();
+            self.tileset_revision = Some(tileset.revision());
+        }
+
+        let chunk_rows = (layer.num_rows() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let chunk_cols = (layer.num_cols() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        while self.layers.len() <= index {
+            self.layers.push(CachedLayer {
+                                 chunk_cols: 0,
+                                 chunks: vec![],
+                             });
+        }
+        let cached_layer = &mut self.layers[index];
+        if cached_layer.chunks.len() != chunk_rows * chunk_cols {
+            cached_layer.chunk_cols = chunk_cols;
+            cached_layer.chunks = (0..chunk_rows * chunk_cols)
+                .map(|_| {
+                         Chunk {
+                             texture: None,
+                             dirty: true,
+                         }
+                     })
+                .collect();
+        }
+
+        let (screen_width, screen_height) = graphics.screen_size();
+        for chunk_row in 0..chunk_rows {
+            for chunk_col in 0..chunk_cols {
+                let dest_rect = Rect::new((chunk_col as u32 * CHUNK_PIXELS) as i32 - offset_x,
+                                          (chunk_row as u32 * CHUNK_PIXELS) as i32 - offset_y,
+                                          CHUNK_PIXELS,
+                                          CHUNK_PIXELS);
+                if dest_rect.right() < 0 || dest_rect.bottom() < 0 ||
+                   dest_rect.left() >= screen_width as i32 ||
+                   dest_rect.top() >= screen_height as i32 {
+                    continue;
+                }
+
+                let chunk = &mut cached_layer.chunks[chunk_row * chunk_cols + chunk_col];
+                if chunk.dirty || chunk.texture.is_none() {
+                    let texture = {
+                        let sheet = match graphics.sprite_sheet(tileset.sheet_path()) {
+                            Some(sheet) => sheet,
+                            None => return,
+                        };
+                        let scratch = self.scratch.get_or_insert_with(new_chunk_surface);
+                        render_chunk(sheet, tileset, layer, chunk_row, chunk_col, scratch);
+                        graphics.create_texture(scratch)
+                    };
+                    chunk.texture = Some(texture);
+                    chunk.dirty = false;
+                }
+                if let Some(ref texture) = chunk.texture {
+                    graphics.draw_texture(texture, None, dest_rect);
+                }
+            }
+        }
+    }
+}
+
+fn new_chunk_surface() -> Surface<'static> {
+    Surface::new(CHUNK_PIXELS, CHUNK_PIXELS, PixelFormatEnum::ARGB8888)
+        .expect("Failed to create tile cache surface")
+}
+
+fn render_chunk(sheet: &Surface,
+                tileset: &Tileset,
+                layer: &Layer,
+                chunk_row: usize,
+                chunk_col: usize,
+                target: &mut Surface) {
+    target
+        .fill_rect(None, Color::RGBA(0, 0, 0, 0))
+        .expect("Failed to clear tile cache surface");
+
+    let first_row = chunk_row * CHUNK_SIZE;
+    let first_col = chunk_col * CHUNK_SIZE;
+    for row in first_row..(first_row + CHUNK_SIZE).min(layer.num_rows()) {
+        for col in first_col..(first_col + CHUNK_SIZE).min(layer.num_cols()) {
+            let index = match layer.tile(row, col).and_then(|tile| tile.index()) {
+                Some(index) if !tileset.is_animated(index) => index,
+                _ => continue,
+            };
+            let dest_rect = Rect::new(((col - first_col) as u32 * TILE_SIZE) as i32,
+                                      ((row - first_row) as u32 * TILE_SIZE) as i32,
+                                      TILE_SIZE,
+                                      TILE_SIZE);
+            sheet
+                .blit(tileset.source_rect(index), target, dest_rect)
+                .expect("Failed to render tile cache");
+        }
+    }
+}
+
+// These open a window, so they need a display or SDL_VIDEODRIVER=dummy
+#[cfg(test)]
+mod benches {
+    extern crate test;
+
+    use super::*;
+    use map::tile::TileType;
+    use sdl2;
+    use self::test::Bencher;
+    use time::Duration;
+
+    const SHEET_PATH: &str = "content/PrtCave.bmp";
+    const MAP_SIZE: usize = 256;
+    const VIEW_COLS: usize = 20;
+    const VIEW_ROWS: usize = 15;
+
+    fn large_map(graphics: &mut Graphics) -> (Tileset, Layer) {
+        let mut tileset = Tileset::new(graphics, SHEET_PATH).unwrap();
+        tileset.set_tile_type(1, TileType::Wall);
+
+        let mut layer = Layer::new("main", MAP_SIZE, MAP_SIZE);
+        for row in 0..MAP_SIZE {
+            for col in 0..MAP_SIZE {
+                *layer.tile_mut(row, col).unwrap() = tileset.tile(((row * 7 + col) % 80) as u16);
+            }
+        }
+        (tileset, layer)
+    }
+
+    fn graphics() -> Graphics {
+        Graphics::new(sdl2::init().unwrap()).unwrap()
+    }
+
+    // What Map::draw did before the cache: one blit per visible tile
+    #[bench]
+    fn bench_draw_every_tile(b: &mut Bencher) {
+        let mut graphics = graphics();
+        let (tileset, layer) = large_map(&mut graphics);
+        b.iter(|| for row in 0..VIEW_ROWS {
+                   for col in 0..VIEW_COLS {
+                       let index = layer.tile(row, col).unwrap().index().unwrap();
+                       tileset.draw_tile(&mut graphics,
+                                         index,
+                                         Duration::zero(),
+                                         row,
+                                         col,
+                                         (col as u32 * TILE_SIZE) as i32,
+                                         (row as u32 * TILE_SIZE) as i32);
+                   }
+               });
+    }
+
+    #[bench]
+    fn bench_draw_cached_chunks(b: &mut Bencher) {
+        let mut graphics = graphics();
+        let (tileset, layer) = large_map(&mut graphics);
+        let mut cache = TileCache::new();
+        // The first draw renders the visible chunks, the rest only copy them
+        cache.draw_layer(&mut graphics, &tileset, 0, &layer, 0, 0);
+        b.iter(|| cache.draw_layer(&mut graphics, &tileset, 0, &layer, 0, 0));
+    }
+
+    // Worst case for the cache: a tile changes in a visible chunk every frame
+    #[bench]
+    fn bench_draw_after_tile_change(b: &mut Bencher) {
+        let mut graphics = graphics();
+        let (tileset, layer) = large_map(&mut graphics);
+        let mut cache = TileCache::new();
+        b.iter(|| {
+                   cache.invalidate(0, 1, 1);
+                   cache.draw_layer(&mut graphics, &tileset, 0, &layer, 0, 0);
+               });
+    }
+}
@@ -0,0 +1,24 @@
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TileChange {
+    layer: usize,
+    row: usize,
+    col: usize,
+}
+
+impl TileChange {
+    pub fn new(layer: usize, row: usize, col: usize) -> TileChange {
+        TileChange { layer, row, col }
+    }
+
+    pub fn layer(&self) -> usize {
+        self.layer
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+}
@@ -0,0 +1,121 @@
+use game::TILE_SIZE;
+use graphics::Graphics;
+use map::tile::{Tile, TileIndex, TileType};
+use map::tile_animation::TileAnimation;
+use sdl2::rect::Rect;
+use time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct TileAttributes {
+    tile_type: TileType,
+    animation: Option<TileAnimation>,
+}
+
+impl TileAttributes {
+    pub fn tile_type(&self) -> TileType {
+        self.tile_type
+    }
+
+    pub fn animation(&self) -> Option<&TileAnimation> {
+        self.animation.as_ref()
+    }
+}
+
+// A sheet of TILE_SIZE tiles, numbered left to right and top to bottom
+pub struct Tileset {
+    sheet_path: String,
+    columns: usize,
+    attributes: Vec<TileAttributes>,
+    // Bumped whenever the attributes change, so caches built from the old ones are thrown away
+    revision: u32,
+}
+
+impl Tileset {
+    // Black is transparent, like every other sheet
+    pub fn new(graphics: &mut Graphics, file_path: &str) -> Result<Tileset, String> {
+        let (width, height) = {
+            let sheet = graphics
+                .try_load_image(file_path)
+                .ok_or_else(|| format!("Failed to load tileset {}", file_path))?;
+            (sheet.width(), sheet.height())
+        };
+        Ok(Tileset::with_size(file_path,
+                              (width / TILE_SIZE) as usize,
+                              (height / TILE_SIZE) as usize))
+    }
+
+    // A tileset for a sheet of known size, without loading it
+    pub fn with_size(file_path: &str, columns: usize, rows: usize) -> Tileset {
+        Tileset {
+            sheet_path: String::from(file_path),
+            columns,
+            attributes: vec![TileAttributes {
+                                 tile_type: TileType::Air,
+                                 animation: None,
+                             };
+                             columns * rows],
+            revision: 0,
+        }
+    }
+
+    pub fn sheet_path(&self) -> &str {
+        &self.sheet_path
+    }
+
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    pub fn attributes(&self, index: TileIndex) -> Option<&TileAttributes> {
+        self.attributes.get(index as usize)
+    }
+
+    pub fn set_tile_type(&mut self, index: TileIndex, tile_type: TileType) {
+        self.attributes[index as usize].tile_type = tile_type;
+        self.revision += 1;
+    }
+
+    pub fn set_animation(&mut self, index: TileIndex, animation: TileAnimation) {
+        self.attributes[index as usize].animation = Some(animation);
+        self.revision += 1;
+    }
+
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    // A map tile showing `index` with the tile type the tileset gives it
+    pub fn tile(&self, index: TileIndex) -> Tile {
+        let tile_type = self.attributes(index)
+            .map_or(TileType::Air, |attributes| attributes.tile_type);
+        Tile::from_index(index, tile_type)
+    }
+
+    pub fn is_animated(&self, index: TileIndex) -> bool {
+        self.attributes(index).map_or(false, |attributes| attributes.animation.is_some())
+    }
+
+    pub fn source_rect(&self, index: TileIndex) -> Rect {
+        let index = index as usize;
+        Rect::new((index % self.columns) as i32 * TILE_SIZE as i32,
+                  (index / self.columns) as i32 * TILE_SIZE as i32,
+                  TILE_SIZE,
+                  TILE_SIZE)
+    }
+
+    pub fn draw_tile(&self,
+                     graphics: &mut Graphics,
+                     index: TileIndex,
+                     clock: Duration,
+                     row: usize,
+                     col: usize,
+                     x: i32,
+                     y: i32) {
+        let frame = match self.attributes(index).and_then(|attributes| attributes.animation()) {
+            Some(animation) => animation.frame_index(clock, row, col),
+            None => index,
+        };
+        let destination_rect = Rect::new(x, y, TILE_SIZE, TILE_SIZE);
+        graphics.blit_surface(&self.sheet_path, self.source_rect(frame), destination_rect);
+    }
+}
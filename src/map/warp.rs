@@ -0,0 +1,114 @@
+use game::TILE_SIZE;
+use sdl2::rect::Rect;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WarpTrigger {
+    // Player presses down to interact while standing in the warp area
+    Door,
+    // Player touches the warp area, usually placed just outside the edge of the map
+    Edge,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WarpDestination {
+    map_name: String,
+    row: usize,
+    col: usize,
+}
+
+impl WarpDestination {
+    pub fn new(map_name: &str, row: usize, col: usize) -> WarpDestination {
+        WarpDestination {
+            map_name: String::from(map_name),
+            row,
+            col,
+        }
+    }
+
+    pub fn map_name(&self) -> &str {
+        &self.map_name
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+}
+
+// Area in tiles that moves the player to another map. Rows and columns may be negative or past
+// the end of the map for edge warps. Doors can be locked until the player has a key item.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Warp {
+    trigger: WarpTrigger,
+    row: i32,
+    col: i32,
+    num_rows: u32,
+    num_cols: u32,
+    destination: WarpDestination,
+    // 0 for none
+    required_item: u16,
+}
+
+impl Warp {
+    pub fn new(trigger: WarpTrigger,
+               row: i32,
+               col: i32,
+               num_rows: u32,
+               num_cols: u32,
+               destination: WarpDestination)
+               -> Warp {
+        Warp {
+            trigger,
+            row,
+            col,
+            num_rows,
+            num_cols,
+            destination,
+            required_item: 0,
+        }
+    }
+
+    pub fn with_required_item(mut self, item: u16) -> Warp {
+        self.required_item = item;
+        self
+    }
+
+    pub fn trigger(&self) -> WarpTrigger {
+        self.trigger
+    }
+
+    pub fn row(&self) -> i32 {
+        self.row
+    }
+
+    pub fn col(&self) -> i32 {
+        self.col
+    }
+
+    pub fn num_rows(&self) -> u32 {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> u32 {
+        self.num_cols
+    }
+
+    pub fn destination(&self) -> &WarpDestination {
+        &self.destination
+    }
+
+    pub fn required_item(&self) -> u16 {
+        self.required_item
+    }
+
+    // Warp area in pixels
+    pub fn area(&self) -> Rect {
+        Rect::new(self.col * TILE_SIZE as i32,
+                  self.row * TILE_SIZE as i32,
+                  self.num_cols * TILE_SIZE,
+                  self.num_rows * TILE_SIZE)
+    }
+}
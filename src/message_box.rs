@@ -0,0 +1,315 @@
+use audio::Audio;
+use graphics::Graphics;
+use input::Input;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use time::Duration;
+
+const CHAR_TIME_MS: i64 = 40;
+const FAST_CHAR_TIME_MS: i64 = 8;
+// Keys that fast forward text while held and confirm once it is all shown
+const CONFIRM_KEYS: &[Keycode] = &[Keycode::Z, Keycode::X];
+
+const BOX_WIDTH: u32 = 560;
+const BOX_HEIGHT: u32 = 96;
+const BOX_MARGIN: i32 = 16;
+const PADDING: i32 = 16;
+const MAX_LINES: usize = 3;
+
+// Faces are 48x48 in the original, drawn at double size in a grid six faces across
+const FACE_PATH: &str = "content/Face.bmp";
+const FACE_SIZE: u32 = 96;
+const FACE_COLS: u16 = 6;
+
+const PROMPT_WIDTH: u32 = 120;
+const PROMPT_HEIGHT: u32 = 40;
+
+const BACKGROUND_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 40,
+    a: 255,
+};
+const BORDER_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const TEXT_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const SELECTED_COLOR: Color = Color {
+    r: 255,
+    g: 220,
+    b: 0,
+    a: 255,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageStyle {
+    // A framed box along the bottom of the screen
+    Bottom,
+    // A framed box along the top
+    Top,
+    // Text along the top with no frame, e.g. for captions over a cutscene
+    TopBare,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Prompt {
+    None,
+    Asking { yes_selected: bool },
+    Answered(bool),
+}
+
+// The dialogue box. Text is revealed a character at a time; scripts print into it, or it can
+// show a few lines on its own that the player dismisses.
+pub struct MessageBox {
+    style: Option<MessageStyle>,
+    text: String,
+    // Characters of `text` shown so far
+    revealed: usize,
+    reveal_time: Duration,
+    face: Option<u16>,
+    face_missing: bool,
+    // Name of an item just received, shown over the box
+    item: Option<String>,
+    prompt: Prompt,
+    // Closed by the player instead of by a script
+    dismissable: bool,
+}
+
+impl MessageBox {
+    pub fn new() -> MessageBox {
+        MessageBox {
+            style: None,
+            text: String::new(),
+            revealed: 0,
+            reveal_time: Duration::zero(),
+            face: None,
+            face_missing: false,
+            item: None,
+            prompt: Prompt::None,
+            dismissable: false,
+        }
+    }
+
+    pub fn open(&mut self, style: MessageStyle) {
+        self.style = Some(style);
+        self.clear();
+        self.dismissable = false;
+    }
+
+    // Shows some lines that stay up until the player presses a confirm key
+    pub fn show(&mut self, lines: &[String]) {
+        self.open(MessageStyle::Bottom);
+        self.print(&lines.join("\n"));
+        self.dismissable = true;
+    }
+
+    pub fn close(&mut self) {
+        self.style = None;
+        self.clear();
+        self.face = None;
+        self.item = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.style.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.revealed = 0;
+        self.prompt = Prompt::None;
+    }
+
+    pub fn print(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    pub fn new_line(&mut self) {
+        self.text.push('\n');
+    }
+
+    // 0 hides the portrait
+    pub fn set_face(&mut self, face: u16) {
+        self.face = if face == 0 { None } else { Some(face) };
+    }
+
+    pub fn show_item(&mut self, name: Option<String>) {
+        self.item = name;
+    }
+
+    // True until every printed character is shown
+    pub fn is_busy(&self) -> bool {
+        self.revealed < self.text.chars().count()
+    }
+
+    pub fn ask_yes_no(&mut self) {
+        self.prompt = Prompt::Asking { yes_selected: true };
+    }
+
+    // Some once the player has picked, and only reported once
+    pub fn take_answer(&mut self) -> Option<bool> {
+        match self.prompt {
+            Prompt::Answered(answer) => {
+                self.prompt = Prompt::None;
+                Some(answer)
+            }
+            _ => None,
+        }
+    }
+
+    // Returns true on the frame the player dismisses a box opened with `show`
+    pub fn update(&mut self, elapsed_time: Duration, input: &Input, audio: &mut Audio) -> bool {
+        if !self.is_open() {
+            return false;
+        }
+
+        if self.is_busy() {
+            let char_time = if input.are_any_keys_held(CONFIRM_KEYS) {
+                Duration::milliseconds(FAST_CHAR_TIME_MS)
+            } else {
+                Duration::milliseconds(CHAR_TIME_MS)
+            };
+            self.reveal_time = self.reveal_time + elapsed_time;
+            let num_chars = self.text.chars().count();
+            let mut ticked = false;
+            while self.reveal_time >= char_time && self.revealed < num_chars {
+                self.reveal_time = self.reveal_time - char_time;
+                self.revealed += 1;
+                ticked = true;
+            }
+            if ticked {
+                audio.play_text_tick();
+            }
+            return false;
+        }
+        self.reveal_time = Duration::zero();
+
+        if let Prompt::Asking { yes_selected } = self.prompt {
+            if input.was_key_pressed(Keycode::Left) || input.was_key_pressed(Keycode::Right) {
+                self.prompt = Prompt::Asking { yes_selected: !yes_selected };
+                audio.play_cursor();
+            } else if input.was_key_pressed(Keycode::Z) {
+                self.prompt = Prompt::Answered(yes_selected);
+            }
+            return false;
+        }
+
+        if self.dismissable && CONFIRM_KEYS.iter().any(|&key| input.was_key_pressed(key)) {
+            self.close();
+            return true;
+        }
+        false
+    }
+
+    pub fn draw(&mut self, graphics: &mut Graphics) {
+        let style = match self.style {
+            Some(style) => style,
+            None => return,
+        };
+        let (screen_width, screen_height) = graphics.screen_size();
+        let box_rect = Rect::new((screen_width - BOX_WIDTH) as i32 / 2,
+                                 match style {
+                                     MessageStyle::Bottom => {
+                                         (screen_height - BOX_HEIGHT) as i32 - BOX_MARGIN
+                                     }
+                                     MessageStyle::Top | MessageStyle::TopBare => BOX_MARGIN,
+                                 },
+                                 BOX_WIDTH,
+                                 BOX_HEIGHT);
+        if style != MessageStyle::TopBare {
+            draw_frame(graphics, box_rect);
+        }
+
+        let mut text_x = box_rect.x() + PADDING;
+        if let Some(face) = self.face {
+            if self.draw_face(graphics, face, box_rect) {
+                text_x += FACE_SIZE as i32;
+            }
+        }
+
+        let text_width = (box_rect.right() - PADDING - text_x) as u32;
+        // Wrap the whole text so words don't jump to the next line halfway through appearing
+        let lines = graphics.font().wrap_with_offsets(&self.text, text_width);
+        let mut visible = vec![];
+        for (offset, line) in lines {
+            if offset >= self.revealed {
+                break;
+            }
+            visible.push(line.chars().take(self.revealed - offset).collect::<String>());
+        }
+        // Older lines scroll off the top
+        let first = visible.len().saturating_sub(MAX_LINES);
+        let line_height = graphics.font().line_height() as i32;
+        for (i, line) in visible[first..].iter().enumerate() {
+            graphics.draw_text(line,
+                               text_x,
+                               box_rect.y() + PADDING + i as i32 * line_height,
+                               TEXT_COLOR);
+        }
+
+        if let Some(ref item) = self.item {
+            let item_width = graphics.font().text_width(item) + 2 * PADDING as u32;
+            let item_rect = Rect::new((screen_width - item_width) as i32 / 2,
+                                      box_rect.y() - PROMPT_HEIGHT as i32 - 4,
+                                      item_width,
+                                      PROMPT_HEIGHT);
+            draw_frame(graphics, item_rect);
+            let text_y = item_rect.y() +
+                         (PROMPT_HEIGHT - graphics.font().line_height()) as i32 / 2;
+            graphics.draw_text(item, item_rect.x() + PADDING, text_y, SELECTED_COLOR);
+        }
+
+        if let Prompt::Asking { yes_selected } = self.prompt {
+            let prompt_rect = Rect::new(box_rect.right() - PROMPT_WIDTH as i32,
+                                        box_rect.y() - PROMPT_HEIGHT as i32 - 4,
+                                        PROMPT_WIDTH,
+                                        PROMPT_HEIGHT);
+            draw_frame(graphics, prompt_rect);
+            let (yes_color, no_color) = if yes_selected {
+                (SELECTED_COLOR, TEXT_COLOR)
+            } else {
+                (TEXT_COLOR, SELECTED_COLOR)
+            };
+            let text_y = prompt_rect.y() +
+                         (PROMPT_HEIGHT - graphics.font().line_height()) as i32 / 2;
+            graphics.draw_text("Yes", prompt_rect.x() + 20, text_y, yes_color);
+            graphics.draw_text("No", prompt_rect.x() + 72, text_y, no_color);
+        }
+    }
+
+    // Returns false when there is no face art to draw
+    fn draw_face(&mut self, graphics: &mut Graphics, face: u16, box_rect: Rect) -> bool {
+        if self.face_missing {
+            return false;
+        }
+        if graphics.try_load_image(FACE_PATH).is_none() {
+            self.face_missing = true;
+            return false;
+        }
+        let source_rect = Rect::new(((face % FACE_COLS) as u32 * FACE_SIZE) as i32,
+                                    ((face / FACE_COLS) as u32 * FACE_SIZE) as i32,
+                                    FACE_SIZE,
+                                    FACE_SIZE);
+        let dest_rect = Rect::new(box_rect.x() + PADDING / 2,
+                                  box_rect.y() + (BOX_HEIGHT - FACE_SIZE) as i32 / 2,
+                                  FACE_SIZE,
+                                  FACE_SIZE);
+        graphics.blit_surface(FACE_PATH, source_rect, dest_rect);
+        true
+    }
+}
+
+fn draw_frame(graphics: &mut Graphics, rect: Rect) {
+    graphics.fill_rect(rect, BORDER_COLOR);
+    let inner = Rect::new(rect.x() + 2, rect.y() + 2, rect.width() - 4, rect.height() - 4);
+    graphics.fill_rect(inner, BACKGROUND_COLOR);
+}
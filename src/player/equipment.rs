@@ -0,0 +1,206 @@
+use graphics::Graphics;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use std::f32::consts::PI;
+use time::Duration;
+
+// Equipment is numbered by bit like the original game's <EQ+ and save files
+pub const BOOSTER_08: u16 = 1;
+pub const MAP_SYSTEM: u16 = 2;
+pub const ARMS_BARRIER: u16 = 4;
+pub const TURBOCHARGE: u16 = 8;
+pub const AIR_TANK: u16 = 16;
+pub const BOOSTER_20: u16 = 32;
+pub const MIMIGA_MASK: u16 = 64;
+pub const WHIMSICAL_STAR: u16 = 128;
+pub const NIKUMARU_COUNTER: u16 = 256;
+
+const NUM_STARS: usize = 3;
+const STAR_ORBIT_RADIUS: f32 = 24.0;
+const STAR_SIZE: u32 = 6;
+// Radians per ms
+const STAR_SPEED: f32 = 0.006;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum BoosterKind {
+    // Slows falling and lifts the player
+    V08,
+    // Flies in whichever direction is held
+    V20,
+}
+
+// What a piece of equipment changes. Every hook defaults to changing nothing, so an effect only
+// implements the ones it cares about.
+pub trait EquipmentEffect {
+    fn flag(&self) -> u16;
+
+    // Damage the player takes
+    fn modify_damage(&self, damage: u16) -> u16 {
+        damage
+    }
+
+    // Lets the player fly by holding jump in the air
+    fn booster(&self) -> Option<BoosterKind> {
+        None
+    }
+
+    fn update(&mut self, elapsed_time: Duration) {
+        let _ = elapsed_time;
+    }
+
+    // Drawn over the player, `center` is the middle of the player in pixels
+    fn draw(&self, graphics: &mut Graphics, center: Point) {
+        let _ = (graphics, center);
+    }
+}
+
+// Equipment that only scripts or other systems look at, e.g. the Map System. The Turbocharge is
+// one of these until there are weapons that fire.
+struct Passive {
+    flag: u16,
+}
+
+impl EquipmentEffect for Passive {
+    fn flag(&self) -> u16 {
+        self.flag
+    }
+}
+
+struct Booster {
+    kind: BoosterKind,
+}
+
+impl EquipmentEffect for Booster {
+    fn flag(&self) -> u16 {
+        match self.kind {
+            BoosterKind::V08 => BOOSTER_08,
+            BoosterKind::V20 => BOOSTER_20,
+        }
+    }
+
+    fn booster(&self) -> Option<BoosterKind> {
+        Some(self.kind)
+    }
+}
+
+struct ArmsBarrier;
+
+impl EquipmentEffect for ArmsBarrier {
+    fn flag(&self) -> u16 {
+        ARMS_BARRIER
+    }
+
+    fn modify_damage(&self, damage: u16) -> u16 {
+        (damage + 1) / 2
+    }
+}
+
+struct WhimsicalStar {
+    angle: f32,
+}
+
+impl EquipmentEffect for WhimsicalStar {
+    fn flag(&self) -> u16 {
+        WHIMSICAL_STAR
+    }
+
+    fn update(&mut self, elapsed_time: Duration) {
+        self.angle = (self.angle + elapsed_time.num_milliseconds() as f32 * STAR_SPEED) %
+                     (2.0 * PI);
+    }
+
+    fn draw(&self, graphics: &mut Graphics, center: Point) {
+        for i in 0..NUM_STARS {
+            let angle = self.angle + i as f32 * 2.0 * PI / NUM_STARS as f32;
+            let x = center.x() + (angle.cos() * STAR_ORBIT_RADIUS) as i32;
+            let y = center.y() + (angle.sin() * STAR_ORBIT_RADIUS) as i32;
+            graphics.fill_rect(Rect::from_center(Point::new(x, y), STAR_SIZE, STAR_SIZE),
+                               Color::RGB(255, 240, 120));
+        }
+    }
+}
+
+fn create_effect(flag: u16) -> Option<Box<EquipmentEffect>> {
+    match flag {
+        ARMS_BARRIER => Some(Box::new(ArmsBarrier)),
+        WHIMSICAL_STAR => Some(Box::new(WhimsicalStar { angle: 0.0 })),
+        BOOSTER_08 => Some(Box::new(Booster { kind: BoosterKind::V08 })),
+        BOOSTER_20 => Some(Box::new(Booster { kind: BoosterKind::V20 })),
+        MAP_SYSTEM | TURBOCHARGE | AIR_TANK | MIMIGA_MASK | NIKUMARU_COUNTER => {
+            Some(Box::new(Passive { flag }))
+        }
+        _ => None,
+    }
+}
+
+// The player's equipment slots, one per kind of equipment
+pub struct Equipment {
+    effects: Vec<Box<EquipmentEffect>>,
+}
+
+impl Equipment {
+    pub fn new() -> Equipment {
+        Equipment { effects: vec![] }
+    }
+
+    // Returns false for numbers that aren't a single known piece of equipment
+    pub fn equip(&mut self, flag: u16) -> bool {
+        if self.is_equipped(flag) {
+            return true;
+        }
+        match create_effect(flag) {
+            Some(effect) => {
+                self.effects.push(effect);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn unequip(&mut self, flag: u16) {
+        self.effects.retain(|effect| effect.flag() != flag);
+    }
+
+    pub fn is_equipped(&self, flag: u16) -> bool {
+        self.effects.iter().any(|effect| effect.flag() == flag)
+    }
+
+    // All equipped flags or'ed together, as the original saves them
+    pub fn flags(&self) -> u16 {
+        self.effects.iter().fold(0, |flags, effect| flags | effect.flag())
+    }
+
+    // Replaces everything equipped with the pieces in `flags`, as loaded from a save. Unknown
+    // bits are dropped.
+    pub fn set_flags(&mut self, flags: u16) {
+        self.effects.clear();
+        for bit in 0..16 {
+            if flags & (1 << bit) != 0 {
+                self.equip(1 << bit);
+            }
+        }
+    }
+
+    // The best Booster equipped
+    pub fn booster(&self) -> Option<BoosterKind> {
+        self.effects.iter().filter_map(|effect| effect.booster()).max()
+    }
+
+    pub fn damage(&self, damage: u16) -> u16 {
+        self.effects
+            .iter()
+            .fold(damage, |damage, effect| effect.modify_damage(damage))
+    }
+
+    pub fn update(&mut self, elapsed_time: Duration) {
+        for effect in self.effects.iter_mut() {
+            effect.update(elapsed_time);
+        }
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics, center: Point) {
+        for effect in self.effects.iter() {
+            effect.draw(graphics, center);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use graphics::Graphics;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use time::Duration;
+
+const PARTICLE_LIFE_MS: f32 = 200.0;
+const PARTICLE_SPEED: f32 = 0.25; // pixels/ms
+const PARTICLE_SIZE: u32 = 6;
+const EMIT_INTERVAL_MS: i64 = 30;
+
+struct Particle {
+    x: f32,
+    y: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    age_ms: f32,
+}
+
+// Puffs of smoke left behind by the Booster. Particles drift away from the player and fade out.
+pub struct Exhaust {
+    particles: Vec<Particle>,
+    since_last_emit: Duration,
+}
+
+impl Exhaust {
+    pub fn new() -> Exhaust {
+        Exhaust {
+            particles: vec![],
+            since_last_emit: Duration::zero(),
+        }
+    }
+
+    // Called every frame the Booster fires. `direction` is the way the exhaust blows, opposite
+    // to the thrust.
+    pub fn emit(&mut self, origin: Point, direction: (f32, f32), elapsed_time: Duration) {
+        self.since_last_emit = self.since_last_emit + elapsed_time;
+        if self.since_last_emit < Duration::milliseconds(EMIT_INTERVAL_MS) {
+            return;
+        }
+        self.since_last_emit = Duration::zero();
+        self.particles
+            .push(Particle {
+                      x: origin.x() as f32,
+                      y: origin.y() as f32,
+                      velocity_x: direction.0 * PARTICLE_SPEED,
+                      velocity_y: direction.1 * PARTICLE_SPEED,
+                      age_ms: 0.0,
+                  });
+    }
+
+    pub fn update(&mut self, elapsed_time: Duration) {
+        let elapsed_time_ms = elapsed_time.num_milliseconds() as f32;
+        for particle in self.particles.iter_mut() {
+            particle.x += particle.velocity_x * elapsed_time_ms;
+            particle.y += particle.velocity_y * elapsed_time_ms;
+            particle.age_ms += elapsed_time_ms;
+        }
+        self.particles.retain(|particle| particle.age_ms < PARTICLE_LIFE_MS);
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics, camera: Point) {
+        for particle in self.particles.iter() {
+            let alpha = 255.0 * (1.0 - particle.age_ms / PARTICLE_LIFE_MS);
+            let center = Point::new(particle.x as i32 - camera.x(),
+                                    particle.y as i32 - camera.y());
+            graphics.fill_rect(Rect::from_center(center, PARTICLE_SIZE, PARTICLE_SIZE),
+                               Color::RGBA(230, 230, 230, alpha.max(0.0) as u8));
+        }
+    }
+}
@@ -1,19 +1,26 @@
 mod collision_info;
+pub mod equipment;
+mod exhaust;
 mod sprite_state;
 
 use game::TILE_SIZE;
 use graphics::Graphics;
 use input::Input;
+use inventory::Inventory;
 use map::Map;
 use map::tile::TileType;
-use sdl2::rect::Rect;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
 use sdl2::keyboard::Keycode;
 use self::collision_info::CollisionInfo;
+use self::equipment::{BoosterKind, Equipment};
+use self::exhaust::Exhaust;
 use self::sprite_state::*;
 use sprite::{Sprite, AnimatedSprite};
 use std::collections::HashMap;
 use time::Duration;
-use units;
+use units::{self, AsPixel};
+use weapon::Weapon;
 
 // Walk Motion
 const WALKING_ACCELERATION: units::Acceleration = units::Acceleration(0.00083007812); // pixels/ms/ms
@@ -29,6 +36,25 @@ const JUMP_SPEED: units::Velocity = units::Velocity(0.25); // pixels/ms
 const AIR_ACCELERATION: units::Acceleration = units::Acceleration(0.0003125); // pixels/ms/ms
 const JUMP_GRAVITY: units::Acceleration = units::Acceleration(0.0003125); // pixels/ms/ms
 
+// Booster motion
+const BOOSTER_FUEL: units::Millis = units::Millis(1000);
+const BOOST_08_ACCELERATION: units::Acceleration = units::Acceleration(0.0012); // pixels/ms/ms
+const BOOST_08_MAX_RISE: units::Velocity = units::Velocity(0.12); // pixels/ms
+const BOOST_20_ACCELERATION: units::Acceleration = units::Acceleration(0.0015); // pixels/ms/ms
+const BOOST_20_MAX_SPEED: units::Velocity = units::Velocity(0.2); // pixels/ms
+
+// Health
+const STARTING_HEALTH: u16 = 3;
+const SPIKE_DAMAGE: u16 = 1;
+// After a hit the player can't be hurt again for a moment, and flickers to show it
+const INVINCIBLE_MS: i64 = 1000;
+const FLICKER_MS: i64 = 50;
+
+// Sparkles rising around the player after a refill
+const REFILL_GLOW_MS: i64 = 600;
+const NUM_REFILL_SPARKLES: i32 = 4;
+const REFILL_SPARKLE_RISE: f32 = 24.0; // pixels over the whole glow
+
 // Sprite Frames
 const CHARACTER_FRAME: i32 = 0;
 
@@ -39,6 +65,8 @@ const FALL_FRAME: i32 = 2;
 const UP_FRAME_OFFSET: i32 = 3;
 const DOWN_FRAME: i32 = 6;
 const BACK_FRAME: i32 = 7;
+const BOOST_FRAME: i32 = 8;
+const BOOST_SIDEWAYS_FRAME: i32 = 9;
 
 // Sprite
 const FILE_PATH: &str = "content/MyChar.bmp";
@@ -47,6 +75,13 @@ const FILE_PATH: &str = "content/MyChar.bmp";
 const WALK_FPS: u32 = 15;
 const NUM_WALK_FRAME: u32 = 3;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BoostDirection {
+    Up,
+    Down,
+    Sideways,
+}
+
 pub struct Player {
     sprites: HashMap<SpriteState, Box<Sprite>>,
     x: units::Game,
@@ -59,13 +94,28 @@ pub struct Player {
     jump_active: bool,
     interacting: bool,
     on_ground: bool,
+    input_suspended: bool,
+    health: u16,
+    max_health: u16,
+    inventory: Inventory,
+    equipment: Equipment,
+    // Jump was pressed in the air and is still held
+    boost_active: bool,
+    booster_fuel: units::Millis,
+    exhaust: Exhaust,
+    refill_glow: Duration,
+    invincible: Duration,
     collision_x: Rect,
     collision_y: Rect,
 }
 
 impl Player {
     pub fn new(graphics: &mut Graphics, x: units::Game, y: units::Game) -> Player {
-        let player = Player {
+        Player::without_sprites(x, y).initialize_sprites(graphics)
+    }
+
+    fn without_sprites(x: units::Game, y: units::Game) -> Player {
+        Player {
             sprites: HashMap::new(),
             x,
             y,
@@ -77,10 +127,19 @@ impl Player {
             jump_active: false,
             interacting: false,
             on_ground: false,
+            input_suspended: false,
+            health: STARTING_HEALTH,
+            max_health: STARTING_HEALTH,
+            inventory: Inventory::new(),
+            equipment: Equipment::new(),
+            boost_active: false,
+            booster_fuel: BOOSTER_FUEL,
+            exhaust: Exhaust::new(),
+            refill_glow: Duration::zero(),
+            invincible: Duration::zero(),
             collision_x: Rect::new(6, 10, 20, 12),
             collision_y: Rect::new(10, 2, 12, 30),
-        };
-        player.initialize_sprites(graphics)
+        }
     }
 
     pub fn start_moving_left(&mut self) {
@@ -112,6 +171,14 @@ impl Player {
         self.interacting = self.on_ground;
     }
 
+    pub fn face_left(&mut self) {
+        self.horizontal_facing = HorizontalFacing::Left;
+    }
+
+    pub fn face_right(&mut self) {
+        self.horizontal_facing = HorizontalFacing::Right;
+    }
+
     pub fn look_horizontal(&mut self) {
         self.vertical_facing = VerticalFacing::Horizontal;
     }
@@ -128,8 +195,142 @@ impl Player {
         self.jump_active = false;
     }
 
-    pub fn draw(&self, graphics: &mut Graphics) {
-        self.sprites[&self.get_sprite_state()].draw(graphics, self.x, self.y);
+    // Stops reading the keyboard, e.g. during transitions. Held movement is released.
+    pub fn suspend_input(&mut self) {
+        self.input_suspended = true;
+        self.stop_moving();
+        self.look_horizontal();
+        self.stop_jump();
+        self.boost_active = false;
+        self.interacting = false;
+    }
+
+    pub fn resume_input(&mut self) {
+        self.input_suspended = false;
+    }
+
+    // Places the player at rest, e.g. after warping into another map
+    pub fn set_position(&mut self, x: units::Game, y: units::Game) {
+        self.x = x;
+        self.y = y;
+        self.velocity_x = units::Velocity(0.0);
+        self.velocity_y = units::Velocity(0.0);
+        self.on_ground = false;
+        self.interacting = false;
+    }
+
+    pub fn position(&self) -> (units::Game, units::Game) {
+        (self.x, self.y)
+    }
+
+    pub fn health(&self) -> u16 {
+        self.health
+    }
+
+    pub fn max_health(&self) -> u16 {
+        self.max_health
+    }
+
+    // Getting hurt also drains experience from the weapon in hand. Hits while the player is still
+    // flickering from the last one are ignored.
+    pub fn take_damage(&mut self, amount: u16) {
+        if self.invincible > Duration::zero() {
+            return;
+        }
+        self.invincible = Duration::milliseconds(INVINCIBLE_MS);
+        let amount = self.equipment.damage(amount);
+        self.health = self.health.saturating_sub(amount);
+        if let Some(weapon) = self.inventory.current_weapon_mut() {
+            weapon.lose_exp(amount * 2);
+        }
+    }
+
+    // Restores health from a save. The flicker from a hit before a retry doesn't carry over.
+    pub fn set_health(&mut self, health: u16, max_health: u16) {
+        self.max_health = max_health;
+        self.health = health.min(max_health);
+        self.invincible = Duration::zero();
+    }
+
+    pub fn heal(&mut self, amount: u16) {
+        self.health = self.health.saturating_add(amount).min(self.max_health);
+    }
+
+    pub fn add_max_health(&mut self, amount: u16) {
+        self.max_health = self.max_health.saturating_add(amount);
+        self.health = self.health.saturating_add(amount);
+    }
+
+    // Full health and ammo, from a refill station
+    pub fn refill(&mut self) {
+        self.health = self.max_health;
+        self.inventory.refill_ammo();
+        self.refill_glow = Duration::milliseconds(REFILL_GLOW_MS);
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    pub fn equipment(&self) -> &Equipment {
+        &self.equipment
+    }
+
+    pub fn equipment_mut(&mut self) -> &mut Equipment {
+        &mut self.equipment
+    }
+
+    // For doors and scripts that need a key item
+    pub fn has_item(&self, item: u16) -> bool {
+        self.inventory.has_item(item)
+    }
+
+    pub fn current_weapon(&self) -> Option<&Weapon> {
+        self.inventory.current_weapon()
+    }
+
+    pub fn is_interacting(&self) -> bool {
+        self.interacting
+    }
+
+    pub fn collision_rect(&self) -> Rect {
+        let (units::Pixel(x), units::Pixel(y)) = (self.x.to_pixel(), self.y.to_pixel());
+        Rect::new(x + self.collision_x.left(),
+                  y + self.collision_y.top(),
+                  self.collision_x.width(),
+                  self.collision_y.height())
+    }
+
+    // `camera` is the top left of the screen in map pixels
+    pub fn draw(&self, graphics: &mut Graphics, camera: Point) {
+        self.exhaust.draw(graphics, camera);
+        let (units::Pixel(x), units::Pixel(y)) = (self.x.to_pixel(), self.y.to_pixel());
+        if (self.invincible.num_milliseconds() / FLICKER_MS) % 2 == 0 {
+            self.sprites[&self.get_sprite_state()].draw(graphics, x - camera.x(), y - camera.y());
+        }
+        self.equipment.draw(graphics, self.collision_rect().center() - camera);
+        self.draw_refill_glow(graphics, camera);
+    }
+
+    fn draw_refill_glow(&self, graphics: &mut Graphics, camera: Point) {
+        if self.refill_glow <= Duration::zero() {
+            return;
+        }
+        let remaining = self.refill_glow.num_milliseconds() as f32 / REFILL_GLOW_MS as f32;
+        let mut rect = self.collision_rect();
+        rect.offset(-camera.x(), -camera.y());
+        let rise = ((1.0 - remaining) * REFILL_SPARKLE_RISE) as i32;
+        let spacing = rect.width() as i32 / (NUM_REFILL_SPARKLES - 1);
+        for i in 0..NUM_REFILL_SPARKLES {
+            // Every other sparkle starts lower so they don't rise in a flat line
+            let y = rect.bottom() - rise - (i % 2) * 8;
+            graphics.fill_rect(Rect::from_center(Point::new(rect.left() + i * spacing, y), 4, 4),
+                               Color::RGBA(255, 255, 200, (255.0 * remaining) as u8));
+        }
     }
 
     pub fn update(&mut self, elapsed_time: Duration, map: &Map, input: &Input) {
@@ -137,43 +338,92 @@ impl Player {
         self.sprites.get_mut(&ss).unwrap().update(elapsed_time);
         let elapsed_time_ms = elapsed_time.num_milliseconds() as f32;
 
-        self.update_key_presses(input);
-        self.update_x(elapsed_time_ms, map);
-        self.update_y(elapsed_time_ms, map);
+        if !self.input_suspended {
+            self.update_key_presses(input);
+        }
+        self.equipment.update(elapsed_time);
+        let boost = self.boost();
+        if self.on_ground {
+            self.booster_fuel = BOOSTER_FUEL;
+            self.boost_active = false;
+        } else if let Some((_, direction)) = boost {
+            self.booster_fuel = self.booster_fuel - units::Millis(elapsed_time.num_milliseconds());
+            let exhaust_direction = match direction {
+                BoostDirection::Up => (0.0, 1.0),
+                BoostDirection::Down => (0.0, -1.0),
+                BoostDirection::Sideways if self.horizontal_facing == HorizontalFacing::Left => {
+                    (1.0, 0.0)
+                }
+                BoostDirection::Sideways => (-1.0, 0.0),
+            };
+            self.exhaust.emit(self.collision_rect().center(), exhaust_direction, elapsed_time);
+        }
+        self.exhaust.update(elapsed_time);
+        if self.refill_glow > Duration::zero() {
+            self.refill_glow = self.refill_glow - elapsed_time;
+        }
+        self.update_x(elapsed_time_ms, map, boost);
+        self.update_y(elapsed_time_ms, map, boost);
+        self.update_health(elapsed_time, map);
     }
 
-    fn update_x(&mut self, elapsed_time_ms: f32, map: &Map) {
-        // update velocity
-        let acceleration_x = if self.acceleration_x < 0 {
-            if self.on_ground {
-                -WALKING_ACCELERATION
-            } else {
-                -AIR_ACCELERATION
-            }
-        } else if self.acceleration_x > 0 {
-            if self.on_ground {
-                WALKING_ACCELERATION
-            } else {
-                AIR_ACCELERATION
-            }
-        } else {
-            0.0
-        };
-        self.velocity_x += acceleration_x * elapsed_time_ms;
+    // Spikes hurt on touch, then not again until the flicker from the last hit runs out
+    fn update_health(&mut self, elapsed_time: Duration, map: &Map) {
+        if self.invincible > Duration::zero() {
+            self.invincible = self.invincible - elapsed_time;
+        }
+        let on_spikes = map.get_colliding_tiles(&self.collision_rect())
+            .iter()
+            .any(|tile| tile.tile_type() == TileType::Spike);
+        if on_spikes {
+            self.take_damage(SPIKE_DAMAGE);
+        }
+    }
 
-        self.velocity_x = if self.acceleration_x < 0 {
-            self.velocity_x.max(-MAX_SPEED_X)
-        } else if self.acceleration_x > 0 {
-            self.velocity_x.min(MAX_SPEED_X)
-        } else if self.on_ground {
-            if self.velocity_x > 0.0 {
-                (self.velocity_x - FRICTION * elapsed_time_ms).max(0.0)
+    // The Booster fires while jump is held after pressing it in the air, until the fuel runs
+    // out. The 2.0 flies in the held direction, the 0.8 only lifts.
+    fn boost(&self) -> Option<(BoosterKind, BoostDirection)> {
+        if !self.boost_active || self.booster_fuel <= units::Millis(0) {
+            return None;
+        }
+        self.equipment
+            .booster()
+            .map(|kind| {
+                let direction = match kind {
+                    BoosterKind::V08 => BoostDirection::Up,
+                    BoosterKind::V20 if self.vertical_facing == VerticalFacing::Up => {
+                        BoostDirection::Up
+                    }
+                    BoosterKind::V20 if self.vertical_facing == VerticalFacing::Down => {
+                        BoostDirection::Down
+                    }
+                    BoosterKind::V20 if self.acceleration_x != units::Acceleration(0.0) => {
+                        BoostDirection::Sideways
+                    }
+                    BoosterKind::V20 => BoostDirection::Up,
+                };
+                (kind, direction)
+            })
+    }
+
+    fn update_x(&mut self,
+                elapsed_time_ms: f32,
+                map: &Map,
+                boost: Option<(BoosterKind, BoostDirection)>) {
+        // update velocity
+        if let Some((_, BoostDirection::Sideways)) = boost {
+            let thrust = if self.horizontal_facing == HorizontalFacing::Left {
+                -BOOST_20_ACCELERATION
             } else {
-                (self.velocity_x + FRICTION * elapsed_time_ms).min(0.0)
-            }
+                BOOST_20_ACCELERATION
+            };
+            let elapsed = units::Millis(elapsed_time_ms as i64);
+            self.velocity_x = min_velocity(max_velocity(self.velocity_x + thrust * elapsed,
+                                                        -BOOST_20_MAX_SPEED),
+                                           BOOST_20_MAX_SPEED);
         } else {
-            self.velocity_x
-        };
+            self.update_velocity_x(elapsed_time_ms);
+        }
 
         // calculate delta
         let delta = (self.velocity_x * elapsed_time_ms).round() as i32;
@@ -214,15 +464,72 @@ impl Player {
         }
     }
 
-    fn update_y(&mut self, elapsed_time_ms: f32, map: &Map) {
-        // Update velocity
-        let gravity = if self.jump_active && self.velocity_y < 0.0 {
-            JUMP_GRAVITY
+    fn update_velocity_x(&mut self, elapsed_time_ms: f32) {
+        let acceleration_x = if self.acceleration_x < 0 {
+            if self.on_ground {
+                -WALKING_ACCELERATION
+            } else {
+                -AIR_ACCELERATION
+            }
+        } else if self.acceleration_x > 0 {
+            if self.on_ground {
+                WALKING_ACCELERATION
+            } else {
+                AIR_ACCELERATION
+            }
         } else {
-            GRAVITY
+            0.0
         };
+        self.velocity_x += acceleration_x * elapsed_time_ms;
 
-        self.velocity_y = (self.velocity_y + gravity * elapsed_time_ms).min(MAX_SPEED_Y);
+        self.velocity_x = if self.acceleration_x < 0 {
+            self.velocity_x.max(-MAX_SPEED_X)
+        } else if self.acceleration_x > 0 {
+            self.velocity_x.min(MAX_SPEED_X)
+        } else if self.on_ground {
+            if self.velocity_x > 0.0 {
+                (self.velocity_x - FRICTION * elapsed_time_ms).max(0.0)
+            } else {
+                (self.velocity_x + FRICTION * elapsed_time_ms).min(0.0)
+            }
+        } else {
+            self.velocity_x
+        };
+    }
+
+    fn update_y(&mut self,
+                elapsed_time_ms: f32,
+                map: &Map,
+                boost: Option<(BoosterKind, BoostDirection)>) {
+        // Update velocity
+        let elapsed = units::Millis(elapsed_time_ms as i64);
+        match boost {
+            Some((BoosterKind::V08, _)) => {
+                self.velocity_y = max_velocity(self.velocity_y - BOOST_08_ACCELERATION * elapsed,
+                                               -BOOST_08_MAX_RISE);
+            }
+            Some((BoosterKind::V20, BoostDirection::Up)) => {
+                self.velocity_y = max_velocity(self.velocity_y - BOOST_20_ACCELERATION * elapsed,
+                                               -BOOST_20_MAX_SPEED);
+            }
+            Some((BoosterKind::V20, BoostDirection::Down)) => {
+                self.velocity_y = min_velocity(self.velocity_y + BOOST_20_ACCELERATION * elapsed,
+                                               BOOST_20_MAX_SPEED);
+            }
+            Some((BoosterKind::V20, BoostDirection::Sideways)) => {
+                // Flying sideways holds altitude
+                self.velocity_y = units::Velocity(0.0);
+            }
+            None => {
+                let gravity = if self.jump_active && self.velocity_y < 0.0 {
+                    JUMP_GRAVITY
+                } else {
+                    GRAVITY
+                };
+
+                self.velocity_y = (self.velocity_y + gravity * elapsed_time_ms).min(MAX_SPEED_Y);
+            }
+        }
 
         //calculate_delta
         let delta = (self.velocity_y * elapsed_time_ms).round() as i32;
@@ -288,10 +595,20 @@ impl Player {
 
         // Player jump
         if input.was_key_pressed(Keycode::Z) {
+            // Pressing jump again in the air lights the Booster
+            self.boost_active = !self.on_ground && self.equipment.booster().is_some();
             self.start_jump();
         } else if input.was_key_released(Keycode::Z) {
+            self.boost_active = false;
             self.stop_jump();
         }
+
+        // Weapon switching, in the order set on the inventory screen
+        if input.was_key_pressed(Keycode::A) {
+            self.inventory.cycle_weapon(-1);
+        } else if input.was_key_pressed(Keycode::S) {
+            self.inventory.cycle_weapon(1);
+        }
     }
 
     fn get_collision_info(&self, rect: Rect, map: &Map) -> CollisionInfo {
@@ -306,6 +623,12 @@ impl Player {
     fn get_sprite_state(&self) -> SpriteState {
         let motion_type = if self.interacting {
             MotionType::Interacting
+        } else if let Some((_, direction)) = self.boost() {
+            if direction == BoostDirection::Sideways {
+                MotionType::BoostingSideways
+            } else {
+                MotionType::Boosting
+            }
         } else if self.on_ground {
             if self.acceleration_x == 0 {
                 MotionType::Standing
@@ -377,9 +700,14 @@ impl Player {
             MotionType::Standing => STAND_FRAME,
             MotionType::Jumping => JUMP_FRAME,
             MotionType::Falling => FALL_FRAME,
+            MotionType::Boosting => BOOST_FRAME,
+            MotionType::BoostingSideways => BOOST_SIDEWAYS_FRAME,
         };
-        let vertical_offset = match sprite_state.vertical_facing() {
-            VerticalFacing::Up => UP_FRAME_OFFSET * tile_size,
+        // The booster frames have no looking up versions
+        let vertical_offset = match (sprite_state.motion_type(), sprite_state.vertical_facing()) {
+            (MotionType::Boosting, _) |
+            (MotionType::BoostingSideways, _) => 0,
+            (_, VerticalFacing::Up) => UP_FRAME_OFFSET * tile_size,
             _ => 0,
         };
         let source_x = frame * tile_size + vertical_offset;
@@ -404,7 +732,9 @@ impl Player {
             _ => {
                 let source_x = if sprite_state.vertical_facing() == VerticalFacing::Down {
                     match sprite_state.motion_type() {
-                        MotionType::Jumping | MotionType::Falling => DOWN_FRAME * tile_size,
+                        MotionType::Jumping | MotionType::Falling | MotionType::Boosting => {
+                            DOWN_FRAME * tile_size
+                        }
                         _ => source_x,
                     }
                 } else {
@@ -425,3 +755,54 @@ impl Player {
         self.sprites.insert(sprite_state, Box::new(sprite));
     }
 }
+
+// Velocity only has a partial order, so these compare the raw values
+fn max_velocity(a: units::Velocity, b: units::Velocity) -> units::Velocity {
+    let (units::Velocity(a), units::Velocity(b)) = (a, b);
+    units::Velocity(a.max(b))
+}
+
+fn min_velocity(a: units::Velocity, b: units::Velocity) -> units::Velocity {
+    let (units::Velocity(a), units::Velocity(b)) = (a, b);
+    units::Velocity(a.min(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::AsciiLegend;
+
+    #[test]
+    fn spikes_hurt_once_until_invincibility_runs_out() {
+        let legend = AsciiLegend::new().with('^', TileType::Spike, None);
+        let map = Map::from_ascii("...
+                                  .^.
+                                  ...",
+                                 &legend)
+                .unwrap();
+        let mut player = Player::without_sprites(units::Game(32.0), units::Game(32.0));
+        assert_eq!(player.health(), STARTING_HEALTH);
+
+        player.update_health(Duration::milliseconds(20), &map);
+        assert_eq!(player.health(), STARTING_HEALTH - SPIKE_DAMAGE);
+
+        for _ in 0..(INVINCIBLE_MS / 20 - 1) {
+            player.update_health(Duration::milliseconds(20), &map);
+            assert_eq!(player.health(), STARTING_HEALTH - SPIKE_DAMAGE);
+        }
+
+        player.update_health(Duration::milliseconds(20), &map);
+        assert_eq!(player.health(), STARTING_HEALTH - 2 * SPIKE_DAMAGE);
+    }
+
+    #[test]
+    fn restoring_health_ends_invincibility() {
+        let mut player = Player::without_sprites(units::Game(0.0), units::Game(0.0));
+        player.take_damage(STARTING_HEALTH);
+        assert_eq!(player.health(), 0);
+
+        player.set_health(STARTING_HEALTH, STARTING_HEALTH);
+        player.take_damage(1);
+        assert_eq!(player.health(), STARTING_HEALTH - 1);
+    }
+}
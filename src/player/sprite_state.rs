@@ -5,13 +5,19 @@ pub enum MotionType {
     Walking,
     Jumping,
     Falling,
+    // Booster thrust straight up or down
+    Boosting,
+    // Booster 2.0 thrust to the side
+    BoostingSideways,
 }
 
-pub const MOTION_TYPES: [MotionType; 5] = [MotionType::Standing,
+pub const MOTION_TYPES: [MotionType; 7] = [MotionType::Standing,
                                            MotionType::Interacting,
                                            MotionType::Walking,
                                            MotionType::Jumping,
-                                           MotionType::Falling];
+                                           MotionType::Falling,
+                                           MotionType::Boosting,
+                                           MotionType::BoostingSideways];
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd)]
 pub enum HorizontalFacing {
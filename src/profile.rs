@@ -0,0 +1,340 @@
+// Reader for the original game's Profile.dat. All values are little endian:
+//
+//   0x000  "Do041220"
+//   0x008  map number            i32
+//   0x00C  music                 i32
+//   0x010  x, y                  i32 x2, in 1/512ths of a pixel, the middle of the player
+//   0x018  direction             i32, 0 left and 2 right
+//   0x01C  max health, stars, health   i16 x3
+//   0x024  current weapon slot   i32
+//   0x02C  equipment             i32, the same bits as ours
+//   0x034  playtime              i32, in frames at 50fps
+//   0x038  weapons               8 x (id, level, exp, max ammo, ammo) i32
+//   0x0D8  items                 32 x i32
+//   0x158  teleporter slots      8 x (stage image, event) i32
+//   0x198  maps seen             128 bytes, one bit per map for the Map System
+//   0x218  "FLAG"
+//   0x21C  flags                 1000 bytes
+//
+// The original draws everything at half our size, so positions are doubled.
+
+use flags::NUM_FLAGS;
+use game::TILE_SIZE;
+use inventory;
+use player::equipment::Equipment;
+use save::{SaveData, SavedWeapon};
+use stage;
+use std::fs::File;
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+use weapon;
+
+pub const PROFILE_PATH: &str = "Profile.dat";
+
+const HEADER: &[u8] = b"Do041220";
+const FLAG_HEADER: &[u8] = b"FLAG";
+const PROFILE_SIZE: usize = 0x604;
+
+const MAP_OFFSET: usize = 0x08;
+const MUSIC_OFFSET: usize = 0x0C;
+const X_OFFSET: usize = 0x10;
+const Y_OFFSET: usize = 0x14;
+const DIRECTION_OFFSET: usize = 0x18;
+const MAX_HEALTH_OFFSET: usize = 0x1C;
+const STARS_OFFSET: usize = 0x1E;
+const HEALTH_OFFSET: usize = 0x20;
+const WEAPON_SLOT_OFFSET: usize = 0x24;
+const EQUIPMENT_OFFSET: usize = 0x2C;
+const PLAYTIME_OFFSET: usize = 0x34;
+const WEAPONS_OFFSET: usize = 0x38;
+const NUM_WEAPON_SLOTS: usize = 8;
+const WEAPON_SIZE: usize = 20;
+const ITEMS_OFFSET: usize = 0xD8;
+const NUM_ITEM_SLOTS: usize = 32;
+const TELEPORTERS_OFFSET: usize = 0x158;
+const NUM_TELEPORTERS: usize = 8;
+const MAPS_SEEN_OFFSET: usize = 0x198;
+const MAPS_SEEN_SIZE: usize = 128;
+const FLAG_HEADER_OFFSET: usize = 0x218;
+const FLAGS_OFFSET: usize = 0x21C;
+
+const SUBPIXELS: f64 = 512.0;
+const SCALE: f64 = 2.0;
+const PLAYTIME_FPS: i32 = 50;
+
+// A converted save, and what was in the profile that this game has nowhere to put
+pub struct Import {
+    pub save: SaveData,
+    pub unsupported: Vec<String>,
+}
+
+pub fn read(path: &str) -> Result<Import, String> {
+    let mut data = vec![];
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut data))
+        .map_err(|e| format!("Failed to read profile {}: {}", path, e))?;
+    import(&data).map_err(|e| format!("Failed to import profile {}: {}", path, e))
+}
+
+pub fn import(data: &[u8]) -> Result<Import, String> {
+    if data.len() < PROFILE_SIZE {
+        return Err(format!("Expected {} bytes but found {}", PROFILE_SIZE, data.len()));
+    }
+    if &data[..HEADER.len()] != HEADER {
+        return Err(String::from("Not a Profile.dat, the header is missing"));
+    }
+    if &data[FLAG_HEADER_OFFSET..FLAG_HEADER_OFFSET + FLAG_HEADER.len()] != FLAG_HEADER {
+        return Err(String::from("The FLAG marker is missing"));
+    }
+
+    let mut unsupported = vec![];
+    let map_number = read_i32(data, MAP_OFFSET);
+    let map = if map_number >= 0 && map_number <= u16::max_value() as i32 {
+        stage::stage_name(map_number as u16)
+    } else {
+        None
+    };
+    let map = map.ok_or_else(|| format!("Map number {} is not known", map_number))?;
+
+    let music = read_i32(data, MUSIC_OFFSET);
+    if music != 0 {
+        unsupported.push(format!("Music {} (there is no music yet)", music));
+    }
+    if read_i32(data, DIRECTION_OFFSET) != 0 {
+        unsupported.push(String::from("Facing right (saves don't keep the facing)"));
+    }
+    let stars = read_i16(data, STARS_OFFSET);
+    if stars != 0 {
+        unsupported.push(format!("{} Whimsical Star stars (stars aren't counted)", stars));
+    }
+
+    // The player's middle in 1/512ths of an original pixel, to our top left corner
+    let half_player = (TILE_SIZE / 2) as f64;
+    let x = read_i32(data, X_OFFSET) as f64 / SUBPIXELS * SCALE - half_player;
+    let y = read_i32(data, Y_OFFSET) as f64 / SUBPIXELS * SCALE - half_player;
+
+    let max_health = read_i16(data, MAX_HEALTH_OFFSET).max(1) as u16;
+    // A profile saved at 0 health would end the game as soon as it's loaded
+    let health = read_i16(data, HEALTH_OFFSET).max(1) as u16;
+
+    let mut weapons = vec![];
+    let weapon_slot = read_i32(data, WEAPON_SLOT_OFFSET).max(0) as usize;
+    let mut current_weapon = weapon_slot;
+    for slot in 0..NUM_WEAPON_SLOTS {
+        let offset = WEAPONS_OFFSET + slot * WEAPON_SIZE;
+        let id = read_i32(data, offset);
+        if id == 0 {
+            continue;
+        }
+        if id < 0 || !weapon::is_known_weapon(id as u16) {
+            unsupported.push(format!("Weapon {} in slot {}", id, slot));
+            // Keep the same weapon in hand once this one is dropped from the list
+            if slot < weapon_slot {
+                current_weapon -= 1;
+            }
+            continue;
+        }
+        let level = read_i32(data, offset + 4).max(1).min(weapon::MAX_LEVEL as i32);
+        weapons.push(SavedWeapon {
+                         id: id as u16,
+                         level: level as u8,
+                         exp: clamp_u16(read_i32(data, offset + 8)),
+                         max_ammo: clamp_u16(read_i32(data, offset + 12)),
+                         ammo: clamp_u16(read_i32(data, offset + 16)),
+                     });
+    }
+
+    let mut items = vec![];
+    for slot in 0..NUM_ITEM_SLOTS {
+        let id = read_i32(data, ITEMS_OFFSET + slot * 4);
+        if id == 0 {
+            continue;
+        }
+        if id > 0 && inventory::is_known_item(id as u16) {
+            items.push(id as u16);
+        } else {
+            unsupported.push(format!("Item {} in slot {}", id, slot));
+        }
+    }
+
+    // Only the bits we have equipment for are kept
+    let equipment_bits = read_i32(data, EQUIPMENT_OFFSET) as u16;
+    let mut known = Equipment::new();
+    known.set_flags(equipment_bits);
+    let unknown_bits = equipment_bits & !known.flags();
+    if unknown_bits != 0 {
+        unsupported.push(format!("Equipment bits {:#x}", unknown_bits));
+    }
+    let equipment = equipment_bits & !unknown_bits;
+
+    let teleporters = (0..NUM_TELEPORTERS)
+        .filter(|&slot| read_i32(data, TELEPORTERS_OFFSET + slot * 8) != 0)
+        .count();
+    if teleporters > 0 {
+        unsupported.push(format!("{} teleporter destinations (there is no teleporter menu)",
+                                 teleporters));
+    }
+    if data[MAPS_SEEN_OFFSET..MAPS_SEEN_OFFSET + MAPS_SEEN_SIZE].iter().any(|&byte| byte != 0) {
+        unsupported.push(String::from("Maps seen by the Map System (there is no map screen)"));
+    }
+
+    let playtime_frames = read_i32(data, PLAYTIME_OFFSET).max(0);
+    Ok(Import {
+           save: SaveData {
+               timestamp: SystemTime::now()
+                   .duration_since(UNIX_EPOCH)
+                   .map(|since| since.as_secs())
+                   .unwrap_or(0),
+               playtime: (playtime_frames / PLAYTIME_FPS) as u64,
+               map: String::from(map),
+               x,
+               y,
+               health: health.min(max_health),
+               max_health,
+               current_weapon: current_weapon.min(weapons.len().saturating_sub(1)),
+               weapons,
+               items,
+               equipment,
+               flags: data[FLAGS_OFFSET..FLAGS_OFFSET + NUM_FLAGS / 8].to_vec(),
+               skip_flags: vec![],
+           },
+           unsupported,
+       })
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    (data[offset] as i32) | (data[offset + 1] as i32) << 8 | (data[offset + 2] as i32) << 16 |
+    (data[offset + 3] as i32) << 24
+}
+
+fn read_i16(data: &[u8], offset: usize) -> i16 {
+    ((data[offset] as u16) | (data[offset + 1] as u16) << 8) as i16
+}
+
+fn clamp_u16(value: i32) -> u16 {
+    value.max(0).min(u16::max_value() as i32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use player::equipment::BOOSTER_20;
+
+    // An empty profile in First Cave with 3 of 3 health
+    fn profile() -> Vec<u8> {
+        let mut data = vec![0; PROFILE_SIZE];
+        data[..HEADER.len()].copy_from_slice(HEADER);
+        data[FLAG_HEADER_OFFSET..FLAG_HEADER_OFFSET + FLAG_HEADER.len()]
+            .copy_from_slice(FLAG_HEADER);
+        write_i32(&mut data, MAP_OFFSET, 12);
+        write_i16(&mut data, MAX_HEALTH_OFFSET, 3);
+        write_i16(&mut data, HEALTH_OFFSET, 3);
+        data
+    }
+
+    fn write_i32(data: &mut [u8], offset: usize, value: i32) {
+        for i in 0..4 {
+            data[offset + i] = (value >> (i * 8)) as u8;
+        }
+    }
+
+    fn write_i16(data: &mut [u8], offset: usize, value: i16) {
+        data[offset] = value as u8;
+        data[offset + 1] = (value >> 8) as u8;
+    }
+
+    fn write_weapon(data: &mut [u8], slot: usize, id: i32) {
+        write_i32(data, WEAPONS_OFFSET + slot * WEAPON_SIZE, id);
+        write_i32(data, WEAPONS_OFFSET + slot * WEAPON_SIZE + 4, 1);
+    }
+
+    #[test]
+    fn empty_profiles_import_cleanly() {
+        let import = import(&profile()).unwrap();
+        assert_eq!(import.save.map, "Cave");
+        assert_eq!((import.save.health, import.save.max_health), (3, 3));
+        assert!(import.unsupported.is_empty());
+    }
+
+    #[test]
+    fn short_files_are_refused() {
+        assert!(import(&profile()[..PROFILE_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn missing_headers_are_refused() {
+        let mut data = profile();
+        data[0] = b'X';
+        assert!(import(&data).is_err());
+
+        let mut data = profile();
+        data[FLAG_HEADER_OFFSET] = b'X';
+        assert!(import(&data).is_err());
+    }
+
+    #[test]
+    fn positions_are_doubled_and_moved_to_the_top_left() {
+        let mut data = profile();
+        write_i32(&mut data, X_OFFSET, 100 * 512);
+        write_i32(&mut data, Y_OFFSET, 64 * 512 + 256);
+
+        let save = import(&data).unwrap().save;
+        assert_eq!((save.x, save.y), (184.0, 113.0));
+    }
+
+    #[test]
+    fn unknown_weapons_and_items_are_unsupported() {
+        let mut data = profile();
+        write_weapon(&mut data, 0, 99);
+        write_weapon(&mut data, 1, 2);
+        write_i32(&mut data, ITEMS_OFFSET, 99);
+        write_i32(&mut data, ITEMS_OFFSET + 4, 1);
+
+        let import = import(&data).unwrap();
+        assert_eq!(import.save.weapons.iter().map(|weapon| weapon.id).collect::<Vec<_>>(),
+                   [2]);
+        assert_eq!(import.save.items, [1]);
+        assert_eq!(import.unsupported, ["Weapon 99 in slot 0", "Item 99 in slot 0"]);
+    }
+
+    #[test]
+    fn the_weapon_in_hand_survives_dropping_earlier_weapons() {
+        let mut data = profile();
+        write_weapon(&mut data, 0, 2);
+        write_weapon(&mut data, 1, 99);
+        write_weapon(&mut data, 2, 3);
+        write_i32(&mut data, WEAPON_SLOT_OFFSET, 2);
+
+        let save = import(&data).unwrap().save;
+        assert_eq!(save.weapons[save.current_weapon].id, 3);
+    }
+
+    #[test]
+    fn unknown_equipment_bits_are_removed() {
+        let mut data = profile();
+        write_i32(&mut data, EQUIPMENT_OFFSET, (BOOSTER_20 | 0x200) as i32);
+
+        let import = import(&data).unwrap();
+        assert_eq!(import.save.equipment, BOOSTER_20);
+        assert_eq!(import.unsupported, ["Equipment bits 0x200"]);
+    }
+
+    #[test]
+    fn health_is_clamped() {
+        let mut data = profile();
+        write_i16(&mut data, MAX_HEALTH_OFFSET, 3);
+        write_i16(&mut data, HEALTH_OFFSET, 50);
+        let save = import(&data).unwrap().save;
+        assert_eq!((save.health, save.max_health), (3, 3));
+
+        write_i16(&mut data, MAX_HEALTH_OFFSET, -4);
+        write_i16(&mut data, HEALTH_OFFSET, 1);
+        let save = import(&data).unwrap().save;
+        assert_eq!((save.health, save.max_health), (1, 1));
+
+        write_i16(&mut data, MAX_HEALTH_OFFSET, 3);
+        write_i16(&mut data, HEALTH_OFFSET, 0);
+        let save = import(&data).unwrap().save;
+        assert_eq!((save.health, save.max_health), (1, 3));
+    }
+}
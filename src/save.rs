@@ -0,0 +1,425 @@
+// Plain text save files, one per slot. Like the map format each line is a directive followed by
+// its arguments, with flag bits written as hex.
+//
+//   version 1
+//   timestamp 1700000000
+//   playtime 5025
+//   map Cave
+//   position 320 256
+//   health 3 3
+//   weapon 2 1 0 0 0
+//   current_weapon 0
+//   item 1
+//   equipment 1
+//   flags 0000ff...
+//   skip_flags Cave 01000000...
+//
+// Unlike maps, saves from other versions are refused. A game saved with different rules would
+// only resume in a broken state. Map names are written as one word, so a save of a map whose name
+// has whitespace is refused instead.
+
+use flags::Flags;
+use inventory::Inventory;
+use player::Player;
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use time::{self, Duration, Timespec};
+use units;
+use weapon::Weapon;
+
+pub const SAVE_VERSION: u32 = 1;
+pub const NUM_SLOTS: usize = 3;
+// Quick saves get a slot of their own so they never overwrite one picked in the save menu
+pub const QUICK_SAVE_SLOT: usize = 0;
+const SAVE_DIRECTORY: &str = "saves";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SavedWeapon {
+    pub id: u16,
+    pub level: u8,
+    pub exp: u16,
+    pub ammo: u16,
+    pub max_ammo: u16,
+}
+
+// Everything needed to resume a game. The map is reloaded from its file, so only its name is
+// kept.
+#[derive(Clone, Debug)]
+pub struct SaveData {
+    // Seconds since the Unix epoch
+    pub timestamp: u64,
+    // Seconds played
+    pub playtime: u64,
+    pub map: String,
+    pub x: f64,
+    pub y: f64,
+    pub health: u16,
+    pub max_health: u16,
+    pub weapons: Vec<SavedWeapon>,
+    pub current_weapon: usize,
+    pub items: Vec<u16>,
+    pub equipment: u16,
+    pub flags: Vec<u8>,
+    pub skip_flags: Vec<(String, Vec<u8>)>,
+}
+
+impl SaveData {
+    pub fn capture(map: &str, player: &Player, flags: &Flags, playtime: Duration) -> SaveData {
+        let (units::Game(x), units::Game(y)) = player.position();
+        let inventory = player.inventory();
+        SaveData {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or(0),
+            playtime: playtime.num_seconds().max(0) as u64,
+            map: String::from(map),
+            x,
+            y,
+            health: player.health(),
+            max_health: player.max_health(),
+            weapons: inventory
+                .weapons()
+                .iter()
+                .map(|weapon| {
+                         SavedWeapon {
+                             id: weapon.id(),
+                             level: weapon.level(),
+                             exp: weapon.exp(),
+                             ammo: weapon.ammo(),
+                             max_ammo: weapon.max_ammo(),
+                         }
+                     })
+                .collect(),
+            current_weapon: inventory.current_weapon_index(),
+            items: inventory.items().to_vec(),
+            equipment: player.equipment().flags(),
+            flags: flags.bytes().to_vec(),
+            skip_flags: flags
+                .skip_bytes()
+                .into_iter()
+                .map(|(name, bits)| (String::from(name), bits.to_vec()))
+                .collect(),
+        }
+    }
+
+    pub fn restore_flags(&self) -> Flags {
+        let mut flags = Flags::new();
+        flags.set_bytes(&self.flags);
+        for &(ref name, ref bits) in self.skip_flags.iter() {
+            flags.set_skip_bytes(name, bits);
+        }
+        flags
+    }
+
+    // Puts the player back as they were saved. Loading the map is left to the caller.
+    pub fn restore_player(&self, player: &mut Player) {
+        let weapons = self.weapons
+            .iter()
+            .map(|saved| {
+                     let mut weapon = Weapon::new(saved.id, saved.max_ammo);
+                     weapon.set_level(saved.level, saved.exp);
+                     weapon.set_ammo(saved.ammo, saved.max_ammo);
+                     weapon
+                 })
+            .collect();
+        *player.inventory_mut() =
+            Inventory::from_parts(self.items.clone(), weapons, self.current_weapon);
+        player.equipment_mut().set_flags(self.equipment);
+        player.set_health(self.health, self.max_health);
+        player.set_position(units::Game(self.x), units::Game(self.y));
+    }
+
+    pub fn playtime(&self) -> Duration {
+        Duration::seconds(self.playtime as i64)
+    }
+
+    // Local time the game was saved, for save menus
+    pub fn date(&self) -> String {
+        time::at(Timespec::new(self.timestamp as i64, 0))
+            .strftime("%Y-%m-%d %H:%M")
+            .map(|date| date.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn to_text(&self) -> Result<String, String> {
+        let mut out = String::new();
+        writeln!(out, "version {}", SAVE_VERSION).unwrap();
+        writeln!(out, "timestamp {}", self.timestamp).unwrap();
+        writeln!(out, "playtime {}", self.playtime).unwrap();
+        writeln!(out, "map {}", word("Map", &self.map)?).unwrap();
+        writeln!(out, "position {} {}", self.x, self.y).unwrap();
+        writeln!(out, "health {} {}", self.health, self.max_health).unwrap();
+        for weapon in self.weapons.iter() {
+            writeln!(out,
+                     "weapon {} {} {} {} {}",
+                     weapon.id,
+                     weapon.level,
+                     weapon.exp,
+                     weapon.ammo,
+                     weapon.max_ammo)
+                    .unwrap();
+        }
+        writeln!(out, "current_weapon {}", self.current_weapon).unwrap();
+        for item in self.items.iter() {
+            writeln!(out, "item {}", item).unwrap();
+        }
+        writeln!(out, "equipment {}", self.equipment).unwrap();
+        writeln!(out, "flags {}", to_hex(&self.flags)).unwrap();
+        for &(ref name, ref bits) in self.skip_flags.iter() {
+            writeln!(out, "skip_flags {} {}", word("Map", name)?, to_hex(bits)).unwrap();
+        }
+        Ok(out)
+    }
+
+    pub fn from_text(text: &str) -> Result<SaveData, String> {
+        let mut save = SaveData {
+            timestamp: 0,
+            playtime: 0,
+            map: String::new(),
+            x: 0.0,
+            y: 0.0,
+            health: 0,
+            max_health: 0,
+            weapons: vec![],
+            current_weapon: 0,
+            items: vec![],
+            equipment: 0,
+            flags: vec![],
+            skip_flags: vec![],
+        };
+        let mut version = None;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let args: Vec<&str> = line.split_whitespace().collect();
+            let at_line = |message: String| format!("Line {}: {}", number + 1, message);
+            if version.is_none() && args[0] != "version" {
+                return Err(at_line(String::from("Expected the save version first")));
+            }
+            match args[0] {
+                "version" => {
+                    let found: u32 = parse_arg(&args, 1).map_err(&at_line)?;
+                    if found != SAVE_VERSION {
+                        return Err(format!("Save version {} is not supported, expected {}",
+                                           found,
+                                           SAVE_VERSION));
+                    }
+                    version = Some(found);
+                }
+                "timestamp" => save.timestamp = parse_arg(&args, 1).map_err(&at_line)?,
+                "playtime" => save.playtime = parse_arg(&args, 1).map_err(&at_line)?,
+                "map" => save.map = String::from(string_arg(&args, 1).map_err(&at_line)?),
+                "position" => {
+                    save.x = parse_arg(&args, 1).map_err(&at_line)?;
+                    save.y = parse_arg(&args, 2).map_err(&at_line)?;
+                }
+                "health" => {
+                    save.health = parse_arg(&args, 1).map_err(&at_line)?;
+                    save.max_health = parse_arg(&args, 2).map_err(&at_line)?;
+                }
+                "weapon" => {
+                    save.weapons
+                        .push(SavedWeapon {
+                                  id: parse_arg(&args, 1).map_err(&at_line)?,
+                                  level: parse_arg(&args, 2).map_err(&at_line)?,
+                                  exp: parse_arg(&args, 3).map_err(&at_line)?,
+                                  ammo: parse_arg(&args, 4).map_err(&at_line)?,
+                                  max_ammo: parse_arg(&args, 5).map_err(&at_line)?,
+                              });
+                }
+                "current_weapon" => {
+                    save.current_weapon = parse_arg(&args, 1).map_err(&at_line)?
+                }
+                "item" => save.items.push(parse_arg(&args, 1).map_err(&at_line)?),
+                "equipment" => save.equipment = parse_arg(&args, 1).map_err(&at_line)?,
+                "flags" => {
+                    save.flags = from_hex(string_arg(&args, 1).map_err(&at_line)?)
+                        .map_err(&at_line)?
+                }
+                "skip_flags" => {
+                    let name = String::from(string_arg(&args, 1).map_err(&at_line)?);
+                    let bits = from_hex(string_arg(&args, 2).map_err(&at_line)?)
+                        .map_err(&at_line)?;
+                    save.skip_flags.push((name, bits));
+                }
+                directive => return Err(at_line(format!("Unknown directive {}", directive))),
+            }
+        }
+
+        if version.is_none() {
+            return Err(String::from("Save file is empty"));
+        }
+        if save.map.is_empty() {
+            return Err(String::from("Save file has no map"));
+        }
+        // Loading a player with no health left would end the game on the first frame
+        if save.health == 0 {
+            return Err(String::from("Save file has no health left"));
+        }
+        Ok(save)
+    }
+}
+
+pub fn slot_path(slot: usize) -> String {
+    format!("{}/slot{}.sav", SAVE_DIRECTORY, slot)
+}
+
+// Written to a temporary file first, so a crash halfway through leaves the old save in the slot
+pub fn save(slot: usize, data: &SaveData) -> Result<(), String> {
+    let path = slot_path(slot);
+    let text = data.to_text()
+        .map_err(|e| format!("Failed to write save {}: {}", path, e))?;
+    let temp_path = format!("{}.tmp", path);
+    fs::create_dir_all(SAVE_DIRECTORY)
+        .and_then(|_| File::create(&temp_path))
+        .and_then(|mut file| {
+                      file.write_all(text.as_bytes())?;
+                      file.sync_all()
+                  })
+        .and_then(|_| fs::rename(&temp_path, &path))
+        .map_err(|e| format!("Failed to write save {}: {}", path, e))
+}
+
+// Ok(None) for an empty slot
+pub fn load(slot: usize) -> Result<Option<SaveData>, String> {
+    let path = slot_path(slot);
+    let mut text = String::new();
+    match File::open(&path).and_then(|mut file| file.read_to_string(&mut text)) {
+        Ok(_) => {}
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read save {}: {}", path, e)),
+    }
+    SaveData::from_text(&text)
+        .map(Some)
+        .map_err(|e| format!("Failed to load save {}: {}", path, e))
+}
+
+// What's in each menu slot, 1 to NUM_SLOTS. Slots that fail to load are reported as errors.
+pub fn list_slots() -> Vec<Result<Option<SaveData>, String>> {
+    (1..NUM_SLOTS + 1).map(load).collect()
+}
+
+// The slot saved to most recently, quick save included, to continue from
+pub fn latest_slot() -> Option<usize> {
+    (0..NUM_SLOTS + 1)
+        .filter_map(|slot| match load(slot) {
+                        Ok(Some(data)) => Some((data.timestamp, slot)),
+                        _ => None,
+                    })
+        .max()
+        .map(|(_, slot)| slot)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    if text.len() % 2 != 0 || !text.is_ascii() {
+        return Err(format!("Invalid hex {}", text));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+                 u8::from_str_radix(&text[i..i + 2], 16)
+                     .map_err(|_| format!("Invalid hex {}", text))
+             })
+        .collect()
+}
+
+// Names are written as a single word so they read back as one argument
+fn word<'a>(what: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err(format!("{} \"{}\" can't be saved, it must be one word", what, value));
+    }
+    Ok(value)
+}
+
+fn string_arg<'a>(args: &[&'a str], position: usize) -> Result<&'a str, String> {
+    args.get(position)
+        .cloned()
+        .ok_or_else(|| format!("{} is missing argument {}", args[0], position))
+}
+
+fn parse_arg<T: FromStr>(args: &[&str], position: usize) -> Result<T, String> {
+    let arg = string_arg(args, position)?;
+    arg.parse()
+        .map_err(|_| format!("Invalid argument {} to {}: {}", position, args[0], arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_save() -> SaveData {
+        SaveData {
+            timestamp: 1700000000,
+            playtime: 5025,
+            map: String::from("first_cave"),
+            x: 320.0,
+            y: 256.5,
+            health: 2,
+            max_health: 3,
+            weapons: vec![SavedWeapon {
+                              id: 2,
+                              level: 1,
+                              exp: 4,
+                              ammo: 0,
+                              max_ammo: 0,
+                          }],
+            current_weapon: 0,
+            items: vec![1, 5],
+            equipment: 33,
+            flags: vec![0, 255, 16],
+            skip_flags: vec![(String::from("hallway"), vec![1, 0])],
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let save = sample_save();
+        let text = save.to_text().unwrap();
+        let loaded = SaveData::from_text(&text).unwrap();
+
+        assert_eq!(loaded.to_text().unwrap(), text);
+        assert_eq!(loaded.map, "first_cave");
+        assert_eq!((loaded.x, loaded.y), (320.0, 256.5));
+        assert_eq!(loaded.weapons, save.weapons);
+        assert_eq!(loaded.flags, save.flags);
+        assert_eq!(loaded.skip_flags, save.skip_flags);
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let text = sample_save()
+            .to_text()
+            .unwrap()
+            .replace(&format!("version {}", SAVE_VERSION),
+                     &format!("version {}", SAVE_VERSION + 1));
+        assert!(SaveData::from_text(&text).is_err());
+    }
+
+    #[test]
+    fn saves_without_health_are_refused() {
+        let mut save = sample_save();
+        save.health = 0;
+        assert!(SaveData::from_text(&save.to_text().unwrap()).is_err());
+
+        let text = sample_save().to_text().unwrap().replace("health 2 3\n", "");
+        assert!(SaveData::from_text(&text).is_err());
+    }
+
+    #[test]
+    fn map_names_with_whitespace_are_refused() {
+        let mut save = sample_save();
+        save.map = String::from("First Cave");
+        assert!(save.to_text().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use audio::Audio;
+use graphics::Graphics;
+use input::Input;
+use save::{self, SaveData};
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+const PANEL_WIDTH: u32 = 448;
+const PADDING: i32 = 16;
+const SLOT_HEIGHT: u32 = 48;
+
+const PANEL_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 40,
+    a: 240,
+};
+const TEXT_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const HEADING_COLOR: Color = Color {
+    r: 140,
+    g: 160,
+    b: 255,
+    a: 255,
+};
+const CURSOR_COLOR: Color = Color {
+    r: 255,
+    g: 220,
+    b: 0,
+    a: 255,
+};
+const ERROR_COLOR: Color = Color {
+    r: 255,
+    g: 100,
+    b: 100,
+    a: 255,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SaveMenuChoice {
+    // Slots are numbered from 1, like their files
+    Slot(usize),
+    Cancel,
+}
+
+// Opened from a save point. Lists every slot with what's in it; Z saves over the chosen slot and
+// X backs out.
+pub struct SaveMenu {
+    open: bool,
+    cursor: usize,
+    slots: Vec<Result<Option<SaveData>, String>>,
+}
+
+impl SaveMenu {
+    pub fn new() -> SaveMenu {
+        SaveMenu {
+            open: false,
+            cursor: 0,
+            slots: vec![],
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // Reads the slots fresh each time, they may have changed since the menu was last open
+    pub fn open(&mut self) {
+        self.open = true;
+        self.slots = save::list_slots();
+        self.cursor = self.cursor.min(self.slots.len().saturating_sub(1));
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub fn update(&mut self, input: &Input, audio: &mut Audio) -> Option<SaveMenuChoice> {
+        if !self.open || self.slots.is_empty() {
+            return None;
+        }
+        let len = self.slots.len();
+        if input.was_key_pressed(Keycode::Up) {
+            self.cursor = (self.cursor + len - 1) % len;
+            audio.play_cursor();
+        } else if input.was_key_pressed(Keycode::Down) {
+            self.cursor = (self.cursor + 1) % len;
+            audio.play_cursor();
+        }
+
+        if input.was_key_pressed(Keycode::Z) {
+            self.close();
+            Some(SaveMenuChoice::Slot(self.cursor + 1))
+        } else if input.was_key_pressed(Keycode::X) {
+            self.close();
+            Some(SaveMenuChoice::Cancel)
+        } else {
+            None
+        }
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics) {
+        if !self.open {
+            return;
+        }
+        let line_height = graphics.font().line_height() as i32;
+        let panel_height = 2 * PADDING as u32 + line_height as u32 + 8 +
+                           self.slots.len() as u32 * SLOT_HEIGHT;
+        let (screen_width, screen_height) = graphics.screen_size();
+        let panel = Rect::new((screen_width - PANEL_WIDTH) as i32 / 2,
+                              (screen_height - panel_height) as i32 / 2,
+                              PANEL_WIDTH,
+                              panel_height);
+        graphics.fill_rect(panel, PANEL_COLOR);
+
+        let x = panel.x() + PADDING;
+        let mut y = panel.y() + PADDING;
+        graphics.draw_text("Save the game?", x, y, HEADING_COLOR);
+        y += line_height + 8;
+        for (i, slot) in self.slots.iter().enumerate() {
+            let color = if i == self.cursor {
+                CURSOR_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            graphics.draw_text(&format!("Slot {}", i + 1), x, y, color);
+            match *slot {
+                Ok(Some(ref data)) => {
+                    let seconds = data.playtime;
+                    let summary = format!("{}  {}:{:02}:{:02}",
+                                          data.map,
+                                          seconds / 3600,
+                                          seconds / 60 % 60,
+                                          seconds % 60);
+                    graphics.draw_text(&summary, x + 96, y, color);
+                    graphics.draw_text(&data.date(), x + 96, y + line_height, color);
+                }
+                Ok(None) => {
+                    graphics.draw_text("Empty", x + 96, y, color);
+                }
+                Err(_) => {
+                    graphics.draw_text("Unreadable, saving replaces it", x + 96, y, ERROR_COLOR);
+                }
+            }
+            y += SLOT_HEIGHT as i32;
+        }
+    }
+}
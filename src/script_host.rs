@@ -0,0 +1,233 @@
+use flags::Flags;
+use input::Input;
+use inventory;
+use map::WarpDestination;
+use message_box::{MessageBox, MessageStyle};
+use player::Player;
+use sdl2::keyboard::Keycode;
+use stage::{self, Stage};
+use weapon;
+use tsc::{unsupported, ScriptError, ScriptHost};
+use units::{AsGame, Tile};
+
+// A <TRA waiting for the game to move the player to another stage
+pub struct Transport {
+    pub destination: WarpDestination,
+    pub event: u16,
+}
+
+// Script state that outlives a single frame
+pub struct ScriptState {
+    message_box: MessageBox,
+    transport: Option<Transport>,
+    // Set by <KEY and <PRI, so closing a message doesn't hand control back early
+    input_locked: bool,
+}
+
+impl ScriptState {
+    pub fn new() -> ScriptState {
+        ScriptState {
+            message_box: MessageBox::new(),
+            transport: None,
+            input_locked: false,
+        }
+    }
+
+    pub fn message_box_mut(&mut self) -> &mut MessageBox {
+        &mut self.message_box
+    }
+
+    pub fn take_transport(&mut self) -> Option<Transport> {
+        self.transport.take()
+    }
+}
+
+pub struct GameScriptHost<'a> {
+    pub state: &'a mut ScriptState,
+    pub stage: &'a mut Stage,
+    pub player: &'a mut Player,
+    pub flags: &'a mut Flags,
+    pub input: &'a Input,
+}
+
+impl<'a> GameScriptHost<'a> {
+    // The player can't move while a message is up
+    fn open_message(&mut self, style: MessageStyle) {
+        self.state.message_box.open(style);
+        self.player.suspend_input();
+    }
+
+    fn close_message(&mut self) {
+        self.state.message_box.close();
+        if !self.state.input_locked {
+            self.player.resume_input();
+        }
+    }
+
+    fn change_tile(&mut self, col: u16, row: u16, index: u16) -> Result<(), ScriptError> {
+        let map = self.stage.map_mut();
+        let layer = map.layer_index("main")
+            .ok_or_else(|| ScriptError::Host(String::from("Map has no main layer")))?;
+        let tile = match map.tileset() {
+            Some(tileset) => tileset.tile(index),
+            None => return Err(ScriptError::Host(String::from("Map has no tileset"))),
+        };
+        map.set_tile(layer, row as usize, col as usize, tile)
+            .map(|_| ())
+            .map_err(ScriptError::Host)
+    }
+}
+
+impl<'a> ScriptHost for GameScriptHost<'a> {
+    fn run_command(&mut self, name: &str, args: &[u16]) -> Result<(), ScriptError> {
+        match name {
+            "MSG" => self.open_message(MessageStyle::Bottom),
+            "MS2" => self.open_message(MessageStyle::TopBare),
+            "MS3" => self.open_message(MessageStyle::Top),
+            "CLR" => self.state.message_box.clear(),
+            "CLO" => self.close_message(),
+            "FAC" => self.state.message_box.set_face(args[0]),
+            "KEY" | "PRI" => {
+                self.state.input_locked = true;
+                self.player.suspend_input();
+            }
+            "FRE" => {
+                self.state.input_locked = false;
+                if !self.state.message_box.is_open() {
+                    self.player.resume_input();
+                }
+            }
+            "LI+" => self.player.heal(args[0]),
+            "ML+" => self.player.add_max_health(args[0]),
+            "IT+" => {
+                if !inventory::is_known_item(args[0]) {
+                    return Err(ScriptError::Host(format!("Unknown item {}", args[0])));
+                }
+                self.player.inventory_mut().add_item(args[0]);
+            }
+            "IT-" => self.player.inventory_mut().remove_item(args[0]),
+            "AM+" => {
+                if !weapon::is_known_weapon(args[0]) {
+                    return Err(ScriptError::Host(format!("Unknown weapon {}", args[0])));
+                }
+                self.player.inventory_mut().add_weapon(args[0], args[1]);
+            }
+            "AM-" => self.player.inventory_mut().remove_weapon(args[0]),
+            "EQ+" => {
+                if !self.player.equipment_mut().equip(args[0]) {
+                    return Err(ScriptError::Host(format!("Unknown equipment {}", args[0])));
+                }
+            }
+            "EQ-" => self.player.equipment_mut().unequip(args[0]),
+            "GIT" => {
+                // Items are numbered from 1000, anything lower is a weapon
+                let name = if args[0] >= 1000 {
+                    inventory::item_name(args[0] - 1000)
+                } else {
+                    weapon::weapon_name(args[0])
+                };
+                self.state
+                    .message_box
+                    .show_item(if name.is_empty() { None } else { Some(String::from(name)) });
+            }
+            "MYD" => {
+                match args[0] {
+                    0 => self.player.face_left(),
+                    2 => self.player.face_right(),
+                    _ => {}
+                }
+            }
+            "MOV" => {
+                self.player
+                    .set_position(Tile(args[0] as usize).to_game(),
+                                  Tile(args[1] as usize).to_game());
+            }
+            "CMP" => {
+                self.change_tile(args[0], args[1], args[2])?;
+            }
+            "SMP" => {
+                let index = self.stage
+                    .map()
+                    .layer_index("main")
+                    .and_then(|layer| {
+                                  self.stage
+                                      .map()
+                                      .tile(layer, args[1] as usize, args[0] as usize)
+                              })
+                    .and_then(|tile| tile.index())
+                    .map(|index| index.saturating_sub(1));
+                if let Some(index) = index {
+                    self.change_tile(args[0], args[1], index)?;
+                }
+            }
+            "FL+" => self.flags.set(args[0]),
+            "FL-" => self.flags.clear(args[0]),
+            "SK+" => self.flags.set_skip(self.stage.name(), args[0]),
+            "SK-" => self.flags.clear_skip(self.stage.name(), args[0]),
+            "TRA" => {
+                let map_name = stage::stage_name(args[0]).ok_or_else(|| {
+                    ScriptError::Host(format!("Unknown map number {}", args[0]))
+                })?;
+                let destination =
+                    WarpDestination::new(map_name, args[3] as usize, args[2] as usize);
+                self.state.transport = Some(Transport {
+                                                destination,
+                                                event: args[1],
+                                            });
+            }
+            _ => return unsupported(name),
+        }
+        Ok(())
+    }
+
+    fn is_message_open(&self) -> bool {
+        self.state.message_box.is_open()
+    }
+
+    fn print_text(&mut self, text: &str) {
+        self.state.message_box.print(text);
+    }
+
+    fn new_line(&mut self) {
+        self.state.message_box.new_line();
+    }
+
+    fn is_text_busy(&self) -> bool {
+        self.state.message_box.is_busy()
+    }
+
+    fn was_confirmed(&mut self) -> bool {
+        !self.state.message_box.is_busy() &&
+        (self.input.was_key_pressed(Keycode::Z) || self.input.was_key_pressed(Keycode::X))
+    }
+
+    fn ask_yes_no(&mut self) -> Result<(), ScriptError> {
+        self.state.message_box.ask_yes_no();
+        Ok(())
+    }
+
+    fn yes_no_answer(&mut self) -> Option<bool> {
+        self.state.message_box.take_answer()
+    }
+
+    fn flag(&self, flag: u16) -> Result<bool, ScriptError> {
+        Ok(self.flags.test(flag))
+    }
+
+    fn skip_flag(&self, flag: u16) -> Result<bool, ScriptError> {
+        Ok(self.flags.test_skip(self.stage.name(), flag))
+    }
+
+    fn has_item(&self, item: u16) -> Result<bool, ScriptError> {
+        Ok(self.player.has_item(item))
+    }
+
+    fn has_weapon(&self, weapon: u16) -> Result<bool, ScriptError> {
+        Ok(self.player.inventory().has_weapon(weapon))
+    }
+
+    fn end_event(&mut self) {
+        self.state.input_locked = false;
+        self.close_message();
+    }
+}
@@ -0,0 +1,135 @@
+use flags::Flags;
+use graphics::Graphics;
+use interaction::{self, Door, Interactions, RefillStation, SavePoint, Scripted};
+use map::{Map, WarpTrigger};
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use tsc::{codec, Script};
+
+pub const TEST_STAGE: &str = "test";
+const STAGE_DIRECTORY: &str = "content/stages";
+// Events shared by every stage
+const HEAD_SCRIPT: &str = "Head";
+
+// Stage names by the map number scripts use, in the original game's order. Stages that haven't
+// been ported yet simply fail to load.
+const STAGE_NAMES: &[&str] = &["0", "Pens1", "Eggs", "EggX", "Egg6", "EggR", "Weed", "Santa",
+                               "Chako", "MazeI", "Sand", "Mimi", "Cave", "Start", "Barr", "Pool",
+                               "Cemet", "Plant", "Shelt", "Comu", "MiBox", "EgEnd1", "Cthu",
+                               "Egg1", "Pens2", "Malco", "WeedS", "WeedD", "Frog", "Curly",
+                               "WeedB", "Stream", "CurlyS", "Jenka1", "Dark", "Gard", "Jenka2",
+                               "SandE"];
+
+pub fn stage_name(number: u16) -> Option<&'static str> {
+    STAGE_NAMES.get(number as usize).cloned()
+}
+
+// One room of the game: its map plus everything that only lives while the player is in it.
+// Leaving a room drops its stage, which unloads all of that state.
+pub struct Stage {
+    name: String,
+    map: Map,
+    interactions: Interactions,
+    script: Script,
+}
+
+impl Stage {
+    // Entities whose flag is already set are left out
+    pub fn load(graphics: &mut Graphics, name: &str, flags: &Flags) -> Result<Stage, String> {
+        let map = if name == TEST_STAGE {
+            Map::create_test_map(graphics)
+        } else {
+            Map::load(graphics, &format!("{}/{}.map", STAGE_DIRECTORY, name))?
+        };
+
+        let mut interactions = Interactions::new();
+        for warp in map.warps().iter().filter(|warp| warp.trigger() == WarpTrigger::Door) {
+            interactions.add(Box::new(Door::from_warp(warp)));
+        }
+        for entity in map.entities()
+                .iter()
+                .filter(|entity| entity.flag() == 0 || !flags.test(entity.flag())) {
+            match entity.kind() {
+                interaction::SAVE_POINT => {
+                    interactions.add(Box::new(SavePoint::from_placement(graphics, entity)))
+                }
+                interaction::REFILL_STATION => {
+                    interactions.add(Box::new(RefillStation::from_placement(graphics, entity)))
+                }
+                _ if entity.event() != 0 => {
+                    interactions.add(Box::new(Scripted::from_placement(entity)))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Stage {
+               name: String::from(name),
+               map,
+               interactions,
+               script: load_script(name)?,
+           })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut Map {
+        &mut self.map
+    }
+
+    pub fn interactions(&self) -> &Interactions {
+        &self.interactions
+    }
+
+    pub fn interactions_mut(&mut self) -> &mut Interactions {
+        &mut self.interactions
+    }
+
+    pub fn script(&self) -> &Script {
+        &self.script
+    }
+}
+
+// The stage's own events followed by the shared ones from Head, which every stage can jump to.
+// The stage's events win where both use a number.
+fn load_script(name: &str) -> Result<Script, String> {
+    let mut script = read_script(name)?.unwrap_or_else(Script::new);
+    if let Some(head) = read_script(HEAD_SCRIPT)? {
+        script.append(head);
+    }
+    Ok(script)
+}
+
+// Original .tsc scripts are decoded, plain text .txt scripts are used as is. None when there is
+// neither.
+fn read_script(name: &str) -> Result<Option<Script>, String> {
+    let tsc_path = format!("{}/{}.tsc", STAGE_DIRECTORY, name);
+    let txt_path = format!("{}/{}.txt", STAGE_DIRECTORY, name);
+    let (path, data) = match read_file(&tsc_path)? {
+        Some(data) => (tsc_path, codec::decode(&data)),
+        None => {
+            match read_file(&txt_path)? {
+                Some(data) => (txt_path, data),
+                None => return Ok(None),
+            }
+        }
+    };
+    Script::parse(&String::from_utf8_lossy(&data))
+        .map(Some)
+        .map_err(|e| format!("Failed to load script {}: {}", path, e))
+}
+
+fn read_file(path: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut data = vec![];
+    match File::open(path).and_then(|mut file| file.read_to_end(&mut data)) {
+        Ok(_) => Ok(Some(data)),
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read script {}: {}", path, e)),
+    }
+}
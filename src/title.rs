@@ -0,0 +1,95 @@
+use audio::Audio;
+use graphics::Graphics;
+use input::Input;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+
+const TITLE: &str = "Cave Story";
+
+const BACKGROUND_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 255,
+};
+const TEXT_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+const CURSOR_COLOR: Color = Color {
+    r: 255,
+    g: 220,
+    b: 0,
+    a: 255,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TitleChoice {
+    // Load the newest save
+    Continue,
+    NewGame,
+    Quit,
+}
+
+// A plain menu over a black screen, shown after quitting out of a game over
+pub struct TitleScreen {
+    options: Vec<TitleChoice>,
+    cursor: usize,
+}
+
+impl TitleScreen {
+    // Continue is only offered when there's a save to continue from
+    pub fn new(has_save: bool) -> TitleScreen {
+        let mut options = vec![];
+        if has_save {
+            options.push(TitleChoice::Continue);
+        }
+        options.push(TitleChoice::NewGame);
+        options.push(TitleChoice::Quit);
+        TitleScreen { options, cursor: 0 }
+    }
+
+    pub fn update(&mut self, input: &Input, audio: &mut Audio) -> Option<TitleChoice> {
+        let len = self.options.len();
+        if input.was_key_pressed(Keycode::Up) {
+            self.cursor = (self.cursor + len - 1) % len;
+            audio.play_cursor();
+        } else if input.was_key_pressed(Keycode::Down) {
+            self.cursor = (self.cursor + 1) % len;
+            audio.play_cursor();
+        }
+        if input.was_key_pressed(Keycode::Z) {
+            Some(self.options[self.cursor])
+        } else {
+            None
+        }
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics) {
+        graphics.fill_screen(BACKGROUND_COLOR);
+        let (screen_width, screen_height) = graphics.screen_size();
+        let line_height = graphics.font().line_height() as i32;
+
+        let x = (screen_width - graphics.font().text_width(TITLE)) as i32 / 2;
+        let mut y = screen_height as i32 / 3;
+        graphics.draw_text(TITLE, x, y, TEXT_COLOR);
+        y = screen_height as i32 / 2;
+        for (i, &option) in self.options.iter().enumerate() {
+            let label = match option {
+                TitleChoice::Continue => "Continue",
+                TitleChoice::NewGame => "New game",
+                TitleChoice::Quit => "Quit",
+            };
+            let color = if i == self.cursor {
+                CURSOR_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            let x = (screen_width - graphics.font().text_width(label)) as i32 / 2;
+            graphics.draw_text(label, x, y, color);
+            y += line_height;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use graphics::Graphics;
+use sdl2::pixels::Color;
+use time::Duration;
+
+const FADE_TIME_MS: i64 = 250;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FadeState {
+    FadingOut,
+    FadingIn,
+    Done,
+}
+
+// Fades the screen to black and back again
+pub struct Fade {
+    state: FadeState,
+    elapsed: Duration,
+}
+
+impl Fade {
+    pub fn new() -> Fade {
+        Fade {
+            state: FadeState::FadingOut,
+            elapsed: Duration::zero(),
+        }
+    }
+
+    // Returns true on the frame the screen becomes fully black, which is when the scene
+    // underneath should be swapped.
+    pub fn update(&mut self, elapsed_time: Duration) -> bool {
+        self.elapsed = self.elapsed + elapsed_time;
+        if self.elapsed < Duration::milliseconds(FADE_TIME_MS) {
+            return false;
+        }
+
+        self.elapsed = Duration::zero();
+        match self.state {
+            FadeState::FadingOut => {
+                self.state = FadeState::FadingIn;
+                true
+            }
+            FadeState::FadingIn | FadeState::Done => {
+                self.state = FadeState::Done;
+                false
+            }
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == FadeState::Done
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics) {
+        let progress = (self.elapsed.num_milliseconds() as f32 / FADE_TIME_MS as f32).min(1.0);
+        let opacity = match self.state {
+            FadeState::FadingOut => progress,
+            FadeState::FadingIn => 1.0 - progress,
+            FadeState::Done => 0.0,
+        };
+        graphics.fill_screen(Color::RGBA(0, 0, 0, (opacity * 255.0) as u8));
+    }
+}
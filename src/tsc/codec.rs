@@ -0,0 +1,55 @@
+// Original .tsc files are obfuscated with a one byte key: the byte in the middle of the file is
+// stored as is and every other byte has it added. A zero middle byte means a key of 7.
+
+const ZERO_KEY: u8 = 7;
+
+pub fn decode(data: &[u8]) -> Vec<u8> {
+    shift(data, |byte, key| byte.wrapping_sub(key))
+}
+
+pub fn encode(text: &[u8]) -> Vec<u8> {
+    shift(text, |byte, key| byte.wrapping_add(key))
+}
+
+fn shift<F>(data: &[u8], apply: F) -> Vec<u8>
+    where F: Fn(u8, u8) -> u8
+{
+    if data.is_empty() {
+        return vec![];
+    }
+    let middle = data.len() / 2;
+    let key = match data[middle] {
+        0 => ZERO_KEY,
+        key => key,
+    };
+    data.iter()
+        .enumerate()
+        .map(|(i, &byte)| if i == middle { byte } else { apply(byte, key) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_undoes_decode() {
+        let odd_length: &[u8] = b"<MSGHello<NOD";
+        let even_length: &[u8] = b"#0200\r\n<END";
+        let zero_middle: &[u8] = &[12, 200, 0, 3, 255];
+        for &data in &[odd_length, even_length, zero_middle] {
+            assert_eq!(encode(&decode(data)), data);
+        }
+    }
+
+    #[test]
+    fn a_zero_middle_byte_means_a_key_of_seven() {
+        assert_eq!(decode(&[10, 0, 3]), [3, 0, 252]);
+        assert_eq!(encode(&[3, 0, 252]), [10, 0, 3]);
+    }
+
+    #[test]
+    fn the_middle_byte_is_the_key() {
+        assert_eq!(decode(&[40, 30, 20, 35]), [20, 10, 20, 15]);
+    }
+}
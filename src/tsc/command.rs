@@ -0,0 +1,121 @@
+// Every command understood by the original engine. Arguments are always four digit numbers
+// separated by colons, e.g. <TRA0012:0090:0005:0008.
+
+pub struct CommandInfo {
+    pub name: &'static str,
+    pub num_args: usize,
+    pub description: &'static str,
+}
+
+macro_rules! commands {
+    ($(($name:expr, $num_args:expr, $description:expr)),*) => {
+        pub const COMMANDS: &[CommandInfo] = &[
+            $(CommandInfo { name: $name, num_args: $num_args, description: $description }),*
+        ];
+    }
+}
+
+commands![("AE+", 0, "Refill all ammo"),
+          ("AM+", 2, "Give weapon W with X ammo"),
+          ("AM-", 1, "Take weapon W"),
+          ("AMJ", 2, "Jump to event X if the player has weapon W"),
+          ("ANP", 3, "Animate entity W with state X facing Y"),
+          ("BOA", 1, "Set the boss state to W"),
+          ("BSL", 1, "Show the boss health bar for entity W"),
+          ("CAT", 0, "Show all text instantly"),
+          ("CIL", 0, "Clear the illustration"),
+          ("CLO", 0, "Close the message box"),
+          ("CLR", 0, "Clear the message box"),
+          ("CMP", 3, "Change tile W:X to tileset index Y"),
+          ("CMU", 1, "Change music to W"),
+          ("CNP", 3, "Change entity W to type X facing Y"),
+          ("CPS", 0, "Stop the propeller sound"),
+          ("CRE", 0, "Roll the credits"),
+          ("CSS", 0, "Stop the stream sound"),
+          ("DNA", 1, "Remove all entities of type W"),
+          ("DNP", 1, "Remove entity W"),
+          ("ECJ", 2, "Jump to event X if an entity with ID W exists"),
+          ("END", 0, "End the event"),
+          ("EQ+", 1, "Equip item W"),
+          ("EQ-", 1, "Unequip item W"),
+          ("ESC", 0, "Quit to the title screen"),
+          ("EVE", 1, "Jump to event W"),
+          ("FAC", 1, "Show face W in the message box"),
+          ("FAI", 1, "Fade in from direction W"),
+          ("FAO", 1, "Fade out towards direction W"),
+          ("FL+", 1, "Set flag W"),
+          ("FL-", 1, "Clear flag W"),
+          ("FLA", 0, "Flash the screen"),
+          ("FLJ", 2, "Jump to event X if flag W is set"),
+          ("FMU", 0, "Fade out the music"),
+          ("FOB", 2, "Focus the camera on boss W over X ticks"),
+          ("FOM", 1, "Focus the camera on the player over W ticks"),
+          ("FON", 2, "Focus the camera on entity W over X ticks"),
+          ("FRE", 0, "Free the player and entities"),
+          ("GIT", 1, "Show item W in the message box"),
+          ("HMC", 0, "Hide the player"),
+          ("INI", 0, "Restart the game"),
+          ("INP", 3, "Change entity W to type X facing Y and mark it interactable"),
+          ("IT+", 1, "Give item W"),
+          ("IT-", 1, "Take item W"),
+          ("ITJ", 2, "Jump to event X if the player has item W"),
+          ("KEY", 0, "Lock player input and hide the HUD"),
+          ("LDP", 0, "Load the saved game"),
+          ("LI+", 1, "Restore W health"),
+          ("ML+", 1, "Raise max health by W"),
+          ("MLP", 0, "Show the map"),
+          ("MM0", 0, "Stop the player's horizontal movement"),
+          ("MNA", 0, "Show the map name"),
+          ("MNP", 4, "Move entity W to X:Y facing Z"),
+          ("MOV", 2, "Move the player to W:X"),
+          ("MPJ", 1, "Jump to event W if the map has been revealed"),
+          ("MP+", 1, "Reveal map W"),
+          ("MS2", 0, "Open the message box at the top without a frame"),
+          ("MS3", 0, "Open the message box at the top"),
+          ("MSG", 0, "Open the message box at the bottom"),
+          ("MYB", 1, "Knock the player back from direction W"),
+          ("MYD", 1, "Make the player face direction W"),
+          ("NCJ", 2, "Jump to event X if an entity of type W exists"),
+          ("NOD", 0, "Wait for the player to press a key"),
+          ("NUM", 1, "Print the number in memory slot W"),
+          ("PRI", 0, "Lock player input and freeze entities"),
+          ("PS+", 2, "Set teleporter slot W to event X"),
+          ("QUA", 1, "Shake the screen for W ticks"),
+          ("RMU", 0, "Resume the previous music"),
+          ("SAT", 0, "Speed up text display"),
+          ("SIL", 1, "Show illustration W"),
+          ("SK+", 1, "Set skip flag W"),
+          ("SK-", 1, "Clear skip flag W"),
+          ("SKJ", 2, "Jump to event X if skip flag W is set"),
+          ("SLP", 0, "Show the teleporter menu"),
+          ("SMC", 0, "Show the player"),
+          ("SMP", 2, "Subtract one from the tileset index at W:X"),
+          ("SNP", 4, "Spawn entity type W at X:Y facing Z"),
+          ("SOU", 1, "Play sound W"),
+          ("SPS", 0, "Start the propeller sound"),
+          ("SSS", 1, "Start the stream sound at volume W"),
+          ("STC", 0, "Save the time counter"),
+          ("SVP", 0, "Save the game"),
+          ("TAM", 3, "Trade weapon W for weapon X with Y ammo"),
+          ("TRA", 4, "Travel to map W, run event X and place the player at Y:Z"),
+          ("TUR", 0, "Show text instantly until the next message box"),
+          ("UNI", 1, "Set the player's movement mode to W"),
+          ("UNJ", 2, "Jump to event X if the movement mode is W"),
+          ("WAI", 1, "Wait W ticks"),
+          ("WAS", 0, "Wait until the player is on the ground"),
+          ("XX1", 1, "Show the island falling"),
+          ("YNJ", 1, "Ask yes or no and jump to event W on no"),
+          ("ZAM", 0, "Reset all weapon levels")];
+
+pub fn find(name: &str) -> Option<&'static CommandInfo> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+// Event numbers a command may jump to, for commands that take one
+pub fn jump_target(name: &str, args: &[u16]) -> Option<u16> {
+    match name {
+        "EVE" | "MPJ" | "YNJ" => args.first().cloned(),
+        "AMJ" | "ECJ" | "FLJ" | "ITJ" | "NCJ" | "SKJ" | "UNJ" => args.get(1).cloned(),
+        _ => None,
+    }
+}
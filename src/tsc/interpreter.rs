@@ -0,0 +1,514 @@
+use std::fmt;
+use time::Duration;
+use tsc::command;
+use tsc::script::{Script, Token};
+
+// The original engine runs scripts at 50 ticks a second
+const TICK_MS: i64 = 20;
+// Commands run in one frame before giving up on an event that never waits, e.g. an <EVE loop
+const MAX_STEPS_PER_FRAME: usize = 1000;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScriptError {
+    UnknownEvent(u16),
+    UnsupportedCommand(String),
+    WrongArgumentCount {
+        command: String,
+        expected: usize,
+        found: usize,
+    },
+    RunawayEvent(u16),
+    Host(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScriptError::UnknownEvent(event) => write!(f, "Event #{:04} does not exist", event),
+            ScriptError::UnsupportedCommand(ref name) => {
+                write!(f, "<{} is not supported yet and was skipped", name)
+            }
+            ScriptError::WrongArgumentCount {
+                ref command,
+                expected,
+                found,
+            } => {
+                write!(f,
+                       "<{} takes {} arguments but was given {}",
+                       command,
+                       expected,
+                       found)
+            }
+            ScriptError::RunawayEvent(event) => {
+                write!(f, "Event #{:04} ran for too long without waiting", event)
+            }
+            ScriptError::Host(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+pub fn unsupported<T>(name: &str) -> Result<T, ScriptError> {
+    Err(ScriptError::UnsupportedCommand(String::from(name)))
+}
+
+// The game as seen by a running script. Flow control is handled by the interpreter, every other
+// command goes to `run_command`; anything a host doesn't implement is reported as unsupported
+// and skipped.
+pub trait ScriptHost {
+    fn run_command(&mut self, name: &str, args: &[u16]) -> Result<(), ScriptError> {
+        let _ = args;
+        unsupported(name)
+    }
+
+    // Message text is only shown while a message box is open
+    fn is_message_open(&self) -> bool;
+    fn print_text(&mut self, text: &str);
+    fn new_line(&mut self);
+
+    // True while text is still being revealed, the script waits for it before going on
+    fn is_text_busy(&self) -> bool {
+        false
+    }
+
+    // Polled while the script is waiting on <NOD
+    fn was_confirmed(&mut self) -> bool;
+
+    fn ask_yes_no(&mut self) -> Result<(), ScriptError> {
+        unsupported("YNJ")
+    }
+
+    // Polled while the script is waiting on <YNJ, None until the player has answered
+    fn yes_no_answer(&mut self) -> Option<bool> {
+        None
+    }
+
+    fn flag(&self, flag: u16) -> Result<bool, ScriptError> {
+        let _ = flag;
+        unsupported("FLJ")
+    }
+
+    fn skip_flag(&self, flag: u16) -> Result<bool, ScriptError> {
+        let _ = flag;
+        unsupported("SKJ")
+    }
+
+    fn has_item(&self, item: u16) -> Result<bool, ScriptError> {
+        let _ = item;
+        unsupported("ITJ")
+    }
+
+    fn has_weapon(&self, weapon: u16) -> Result<bool, ScriptError> {
+        let _ = weapon;
+        unsupported("AMJ")
+    }
+
+    // Called when an event finishes, to release whatever the event locked
+    fn end_event(&mut self) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Idle,
+    Running,
+    Waiting(Duration),
+    WaitingForText,
+    WaitingForConfirm,
+    WaitingForAnswer(u16),
+}
+
+pub struct Interpreter {
+    script: Script,
+    event: u16,
+    position: usize,
+    state: State,
+}
+
+impl Interpreter {
+    pub fn new(script: Script) -> Interpreter {
+        Interpreter {
+            script,
+            event: 0,
+            position: 0,
+            state: State::Idle,
+        }
+    }
+
+    // Swaps in another script, e.g. after moving to a new stage. Stops the running event.
+    pub fn set_script(&mut self, script: Script) {
+        self.script = script;
+        self.state = State::Idle;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    pub fn current_event(&self) -> Option<u16> {
+        if self.is_running() {
+            Some(self.event)
+        } else {
+            None
+        }
+    }
+
+    pub fn start_event(&mut self, number: u16) -> Result<(), ScriptError> {
+        if self.script.event(number).is_none() {
+            self.state = State::Idle;
+            return Err(ScriptError::UnknownEvent(number));
+        }
+        self.event = number;
+        self.position = 0;
+        self.state = State::Running;
+        Ok(())
+    }
+
+    // Runs the current event until it has to wait for something. Errors are returned for the
+    // caller to report; unsupported commands are skipped and the event carries on.
+    pub fn update(&mut self,
+                  host: &mut dyn ScriptHost,
+                  elapsed_time: Duration)
+                  -> Vec<ScriptError> {
+        let mut errors = vec![];
+        let state = self.state;
+        self.state = match state {
+            State::Waiting(remaining) => {
+                let remaining = remaining - elapsed_time;
+                if remaining > Duration::zero() {
+                    State::Waiting(remaining)
+                } else {
+                    State::Running
+                }
+            }
+            State::WaitingForText if !host.is_text_busy() => State::Running,
+            State::WaitingForConfirm if host.was_confirmed() => State::Running,
+            State::WaitingForAnswer(no_event) => {
+                match host.yes_no_answer() {
+                    Some(true) => State::Running,
+                    Some(false) => self.jump(host, no_event, &mut errors),
+                    None => State::WaitingForAnswer(no_event),
+                }
+            }
+            state => state,
+        };
+
+        let mut steps = 0;
+        while self.state == State::Running {
+            steps += 1;
+            if steps > MAX_STEPS_PER_FRAME {
+                errors.push(ScriptError::RunawayEvent(self.event));
+                self.finish(host);
+                break;
+            }
+
+            let token = match self.script
+                      .event(self.event)
+                      .and_then(|event| event.tokens().get(self.position)) {
+                Some((token, _)) => token.clone(),
+                None => {
+                    // Ran off the end of the event without an <END
+                    self.finish(host);
+                    break;
+                }
+            };
+            self.position += 1;
+
+            match token {
+                Token::Text(ref text) => {
+                    if host.is_message_open() {
+                        host.print_text(text);
+                        if host.is_text_busy() {
+                            self.state = State::WaitingForText;
+                        }
+                    }
+                }
+                Token::NewLine => {
+                    if host.is_message_open() {
+                        host.new_line();
+                    }
+                }
+                Token::Command(ref name, ref args) => {
+                    if let Err(e) = self.run_command(host, name, args) {
+                        errors.push(e);
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn run_command(&mut self,
+                   host: &mut dyn ScriptHost,
+                   name: &str,
+                   args: &[u16])
+                   -> Result<(), ScriptError> {
+        let expected = command::find(name).map(|command| command.num_args);
+        match expected {
+            Some(expected) if expected != args.len() => {
+                return Err(ScriptError::WrongArgumentCount {
+                               command: String::from(name),
+                               expected,
+                               found: args.len(),
+                           });
+            }
+            None => return unsupported(name),
+            _ => {}
+        }
+
+        let mut errors = vec![];
+        match name {
+            "END" => self.finish(host),
+            "EVE" => self.state = self.jump(host, args[0], &mut errors),
+            "WAI" => self.state = State::Waiting(Duration::milliseconds(args[0] as i64 * TICK_MS)),
+            "NOD" => self.state = State::WaitingForConfirm,
+            "YNJ" => {
+                host.ask_yes_no()?;
+                self.state = State::WaitingForAnswer(args[0]);
+            }
+            "FLJ" => {
+                let condition = host.flag(args[0])?;
+                self.jump_if(host, condition, args[1], &mut errors);
+            }
+            "SKJ" => {
+                let condition = host.skip_flag(args[0])?;
+                self.jump_if(host, condition, args[1], &mut errors);
+            }
+            "ITJ" => {
+                let condition = host.has_item(args[0])?;
+                self.jump_if(host, condition, args[1], &mut errors);
+            }
+            "AMJ" => {
+                let condition = host.has_weapon(args[0])?;
+                self.jump_if(host, condition, args[1], &mut errors);
+            }
+            "TRA" | "ESC" | "INI" | "LDP" => {
+                // These leave the current map, the host starts whatever runs next
+                host.run_command(name, args)?;
+                self.state = State::Idle;
+            }
+            _ => host.run_command(name, args)?,
+        }
+
+        match errors.pop() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn jump_if(&mut self,
+               host: &mut dyn ScriptHost,
+               condition: bool,
+               event: u16,
+               errors: &mut Vec<ScriptError>) {
+        if condition {
+            self.state = self.jump(host, event, errors);
+        }
+    }
+
+    // Continues with another event, ending the script if it doesn't exist
+    fn jump(&mut self,
+            host: &mut dyn ScriptHost,
+            event: u16,
+            errors: &mut Vec<ScriptError>)
+            -> State {
+        match self.start_event(event) {
+            Ok(()) => State::Running,
+            Err(e) => {
+                errors.push(e);
+                self.finish(host);
+                State::Idle
+            }
+        }
+    }
+
+    fn finish(&mut self, host: &mut dyn ScriptHost) {
+        self.state = State::Idle;
+        host.end_event();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestHost {
+        message_open: bool,
+        text_busy: bool,
+        confirmed: bool,
+        answer: Option<bool>,
+        commands: Vec<String>,
+        text: String,
+        ended: usize,
+    }
+
+    impl ScriptHost for TestHost {
+        fn run_command(&mut self, name: &str, _: &[u16]) -> Result<(), ScriptError> {
+            if name == "CMU" {
+                return unsupported(name);
+            }
+            self.commands.push(String::from(name));
+            Ok(())
+        }
+
+        fn is_message_open(&self) -> bool {
+            self.message_open
+        }
+
+        fn print_text(&mut self, text: &str) {
+            self.text.push_str(text);
+        }
+
+        fn new_line(&mut self) {
+            self.text.push('\n');
+        }
+
+        fn is_text_busy(&self) -> bool {
+            self.text_busy
+        }
+
+        fn was_confirmed(&mut self) -> bool {
+            self.confirmed
+        }
+
+        fn ask_yes_no(&mut self) -> Result<(), ScriptError> {
+            Ok(())
+        }
+
+        fn yes_no_answer(&mut self) -> Option<bool> {
+            self.answer
+        }
+
+        fn end_event(&mut self) {
+            self.ended += 1;
+        }
+    }
+
+    fn start(text: &str) -> Interpreter {
+        let mut interpreter = Interpreter::new(Script::parse(text).unwrap());
+        interpreter.start_event(1).unwrap();
+        interpreter
+    }
+
+    fn update(interpreter: &mut Interpreter, host: &mut TestHost, ms: i64) -> Vec<ScriptError> {
+        interpreter.update(host, Duration::milliseconds(ms))
+    }
+
+    #[test]
+    fn wai_counts_down_in_ticks() {
+        let mut interpreter = start("#0001\n<WAI0005<END");
+        let mut host = TestHost::default();
+
+        assert!(update(&mut interpreter, &mut host, 0).is_empty());
+        update(&mut interpreter, &mut host, 60);
+        assert!(interpreter.is_running());
+        update(&mut interpreter, &mut host, 40);
+        assert!(!interpreter.is_running());
+        assert_eq!(host.ended, 1);
+    }
+
+    #[test]
+    fn nod_waits_for_confirmation() {
+        let mut interpreter = start("#0001\n<NOD<FL+0001<END");
+        let mut host = TestHost::default();
+
+        update(&mut interpreter, &mut host, 20);
+        update(&mut interpreter, &mut host, 20);
+        assert!(interpreter.is_running());
+        assert!(host.commands.is_empty());
+
+        host.confirmed = true;
+        update(&mut interpreter, &mut host, 20);
+        assert!(!interpreter.is_running());
+        assert_eq!(host.commands, ["FL+"]);
+    }
+
+    #[test]
+    fn busy_text_is_waited_for() {
+        let mut interpreter = start("#0001\nHello<FL+0001\nthere<END");
+        let mut host = TestHost {
+            message_open: true,
+            text_busy: true,
+            ..TestHost::default()
+        };
+
+        update(&mut interpreter, &mut host, 20);
+        update(&mut interpreter, &mut host, 20);
+        assert_eq!(host.text, "Hello");
+        assert!(host.commands.is_empty());
+
+        host.text_busy = false;
+        update(&mut interpreter, &mut host, 20);
+        assert_eq!(host.text, "Hello\nthere");
+        assert_eq!(host.commands, ["FL+"]);
+        assert_eq!(host.ended, 1);
+    }
+
+    #[test]
+    fn text_is_dropped_without_a_message_box() {
+        let mut interpreter = start("#0001\nHello<END");
+        let mut host = TestHost {
+            text_busy: true,
+            ..TestHost::default()
+        };
+
+        update(&mut interpreter, &mut host, 20);
+        assert_eq!(host.text, "");
+        assert!(!interpreter.is_running());
+    }
+
+    #[test]
+    fn ynj_goes_on_for_yes_and_jumps_for_no() {
+        let text = "#0001\n<YNJ0002<FL+0001<END\n#0002\n<FL-0001<END";
+        for &(answer, command) in &[(true, "FL+"), (false, "FL-")] {
+            let mut interpreter = start(text);
+            let mut host = TestHost::default();
+
+            update(&mut interpreter, &mut host, 20);
+            assert!(interpreter.is_running());
+            assert!(host.commands.is_empty());
+
+            host.answer = Some(answer);
+            assert!(update(&mut interpreter, &mut host, 20).is_empty());
+            assert_eq!(host.commands, [command]);
+            assert_eq!(host.ended, 1);
+        }
+    }
+
+    #[test]
+    fn runaway_eve_loops_are_stopped() {
+        let mut interpreter = start("#0001\n<EVE0001");
+        let mut host = TestHost::default();
+
+        let errors = update(&mut interpreter, &mut host, 20);
+        assert_eq!(errors, [ScriptError::RunawayEvent(1)]);
+        assert!(!interpreter.is_running());
+        assert_eq!(host.ended, 1);
+    }
+
+    #[test]
+    fn unsupported_commands_are_skipped() {
+        let mut interpreter = start("#0001\n<ZZZ0001<CMU0002<FL+0001<END");
+        let mut host = TestHost::default();
+
+        let errors = update(&mut interpreter, &mut host, 20);
+        assert_eq!(errors,
+                   [ScriptError::UnsupportedCommand(String::from("ZZZ")),
+                    ScriptError::UnsupportedCommand(String::from("CMU"))]);
+        assert_eq!(host.commands, ["FL+"]);
+        assert_eq!(host.ended, 1);
+    }
+
+    #[test]
+    fn leaving_the_map_does_not_end_the_event() {
+        for text in &["#0001\n<TRA0012:0090:0005:0008<FL+0001<END", "#0001\n<ESC<FL+0001<END"] {
+            let mut interpreter = start(text);
+            let mut host = TestHost::default();
+
+            assert!(update(&mut interpreter, &mut host, 20).is_empty());
+            assert!(!interpreter.is_running());
+            assert_eq!(host.commands.len(), 1);
+            assert_eq!(host.ended, 0);
+        }
+    }
+}
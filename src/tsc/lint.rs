@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fmt;
+use super::command;
+use super::script::{Script, Token};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintMessage {
+    line: usize,
+    message: String,
+}
+
+impl LintMessage {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for LintMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Checks a decoded script for unknown commands, wrong argument counts, jumps to events that
+// don't exist and event numbers used more than once. Events in `shared`, e.g. Head.tsc, can be
+// jumped to as well.
+pub fn lint(text: &str, shared: &[Script]) -> Vec<LintMessage> {
+    let script = match Script::parse(text) {
+        Ok(script) => script,
+        Err(e) => {
+            return vec![LintMessage {
+                            line: 0,
+                            message: e,
+                        }]
+        }
+    };
+
+    let mut messages = vec![];
+    let mut first_lines = HashMap::new();
+    for event in script.events() {
+        if let Some(first_line) = first_lines.insert(event.number(), event.line().0) {
+            messages.push(LintMessage {
+                              line: event.line().0,
+                              message: format!("Event #{:04} is already defined on line {}",
+                                               event.number(),
+                                               first_line),
+                          });
+            // Keep pointing at the first definition, that's the one the game runs
+            first_lines.insert(event.number(), first_line);
+        }
+    }
+
+    for event in script.events() {
+        let tokens = event.tokens();
+        for (position, &(ref token, line)) in tokens.iter().enumerate() {
+            let (name, args) = match *token {
+                Token::Command(ref name, ref args) => (name, args),
+                _ => continue,
+            };
+            let mut report = |message: String| {
+                messages.push(LintMessage {
+                                  line: line.0,
+                                  message,
+                              })
+            };
+
+            let info = match command::find(name) {
+                Some(info) => info,
+                None => {
+                    report(format!("Unknown command <{}", name));
+                    continue;
+                }
+            };
+            let extra_args = match tokens.get(position + 1) {
+                Some(&(Token::Text(ref text), _)) => count_extra_args(text),
+                _ => 0,
+            };
+            if args.len() != info.num_args || extra_args > 0 {
+                report(format!("<{} takes {} arguments but was given {}",
+                               name,
+                               info.num_args,
+                               args.len() + extra_args));
+            }
+            if let Some(target) = command::jump_target(name, args) {
+                let is_defined = script.event(target).is_some() ||
+                                 shared.iter().any(|other| other.event(target).is_some());
+                if !is_defined {
+                    report(format!("<{} jumps to event #{:04} which is not defined",
+                                   name,
+                                   target));
+                }
+            }
+        }
+    }
+
+    messages.sort_by_key(|message| message.line);
+    messages
+}
+
+// Arguments past a command's count end up at the start of the text after it, e.g. ":0001"
+fn count_extra_args(text: &str) -> usize {
+    let mut count = 0;
+    let mut rest = text;
+    while rest.starts_with(':') &&
+          rest.get(1..5)
+              .map_or(false, |digits| digits.chars().all(|ch| ch.is_ascii_digit())) {
+        count += 1;
+        rest = &rest[5..];
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(text: &str, shared: &[Script]) -> Vec<(usize, String)> {
+        lint(text, shared)
+            .into_iter()
+            .map(|message| (message.line(), String::from(message.message())))
+            .collect()
+    }
+
+    fn one_message(line: usize, message: &str) -> Vec<(usize, String)> {
+        vec![(line, String::from(message))]
+    }
+
+    #[test]
+    fn clean_scripts_have_no_messages() {
+        assert!(messages("#0001\n<MSGHi<NOD<FLJ0010:0002<END\n#0002\n<END", &[]).is_empty());
+    }
+
+    #[test]
+    fn unknown_commands_are_reported() {
+        assert_eq!(messages("#0001\n<ZZZ<END", &[]),
+                   one_message(2, "Unknown command <ZZZ"));
+    }
+
+    #[test]
+    fn missing_arguments_are_reported() {
+        assert_eq!(messages("#0001\n<WAI<END", &[]),
+                   one_message(2, "<WAI takes 1 arguments but was given 0"));
+    }
+
+    #[test]
+    fn extra_arguments_are_reported() {
+        assert_eq!(messages("#0001\n<WAI0010:0020:0030<END", &[]),
+                   one_message(2, "<WAI takes 1 arguments but was given 3"));
+    }
+
+    #[test]
+    fn jumps_to_undefined_events_are_reported() {
+        assert_eq!(messages("#0001\n<FLJ0010:0002<END", &[]),
+                   one_message(2, "<FLJ jumps to event #0002 which is not defined"));
+    }
+
+    #[test]
+    fn jumps_into_shared_scripts_are_allowed() {
+        let head = Script::parse("#0016\n<END").unwrap();
+        assert!(messages("#0001\n<EVE0016", &[head]).is_empty());
+    }
+
+    #[test]
+    fn duplicate_events_point_at_the_first_definition() {
+        assert_eq!(messages("#0001\n<END\n#0001\n<END\n#0001\n<END", &[]),
+                   vec![(3, String::from("Event #0001 is already defined on line 1")),
+                        (5, String::from("Event #0001 is already defined on line 1"))]);
+    }
+}
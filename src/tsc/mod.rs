@@ -0,0 +1,8 @@
+pub mod codec;
+pub mod command;
+mod interpreter;
+pub mod lint;
+mod script;
+
+pub use self::interpreter::{unsupported, Interpreter, ScriptError, ScriptHost};
+pub use self::script::{Event, Script, SourceLine, Token};
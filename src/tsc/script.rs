@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use super::command;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Token {
+    Command(String, Vec<u16>),
+    Text(String),
+    NewLine,
+}
+
+// Where a token came from, for error messages
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceLine(pub usize);
+
+#[derive(Clone, Debug)]
+pub struct Event {
+    number: u16,
+    line: SourceLine,
+    tokens: Vec<(Token, SourceLine)>,
+}
+
+impl Event {
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    pub fn line(&self) -> SourceLine {
+        self.line
+    }
+
+    pub fn tokens(&self) -> &[(Token, SourceLine)] {
+        &self.tokens
+    }
+}
+
+// A decoded TSC script: events start with `#NNNN` at the start of a line and run until the
+// next event. Within an event, `<XXX` starts a command and anything else is message text.
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+    events: Vec<Event>,
+    index: HashMap<u16, usize>,
+}
+
+impl Script {
+    pub fn new() -> Script {
+        Script {
+            events: vec![],
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Script, String> {
+        let mut script = Script::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number = SourceLine(line_index + 1);
+            let line = line.trim_end_matches('\r');
+            if line.starts_with('#') {
+                let digits: String = line[1..].chars().take(4).collect();
+                let number = parse_number(&digits)
+                    .ok_or_else(|| {
+                                    format!("Line {}: Invalid event number {}", line_number.0, line)
+                                })?;
+                script.events.push(Event {
+                                       number,
+                                       line: line_number,
+                                       tokens: vec![],
+                                   });
+                continue;
+            }
+
+            let event = match script.events.last_mut() {
+                Some(event) => event,
+                // Anything before the first event is ignored by the original engine too
+                None => continue,
+            };
+            parse_line(line, line_number, &mut event.tokens);
+            event.tokens.push((Token::NewLine, line_number));
+        }
+
+        // Later duplicates are unreachable, the first event with a number wins
+        for (position, event) in script.events.iter().enumerate() {
+            script.index.entry(event.number).or_insert(position);
+        }
+        Ok(script)
+    }
+
+    // Adds the events of `other`, e.g. the shared Head.tsc events to every stage script
+    pub fn append(&mut self, other: Script) {
+        for event in other.events {
+            self.index.entry(event.number).or_insert(self.events.len());
+            self.events.push(event);
+        }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn event(&self, number: u16) -> Option<&Event> {
+        self.index.get(&number).map(|&position| &self.events[position])
+    }
+}
+
+fn parse_line(line: &str, line_number: SourceLine, tokens: &mut Vec<(Token, SourceLine)>) {
+    let mut text = String::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        if rest.starts_with('<') && rest.len() >= 4 && rest.is_char_boundary(4) {
+            if !text.is_empty() {
+                tokens.push((Token::Text(text.clone()), line_number));
+                text.clear();
+            }
+            let name = &rest[1..4];
+            rest = &rest[4..];
+            let args = parse_args(name, &mut rest);
+            tokens.push((Token::Command(String::from(name), args), line_number));
+        } else {
+            let ch = rest.chars().next().unwrap();
+            text.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    if !text.is_empty() {
+        tokens.push((Token::Text(text), line_number));
+    }
+}
+
+// Known commands take exactly their argument count. Unknown ones take every `NNNN` group that
+// follows so the text after them still lines up.
+fn parse_args(name: &str, rest: &mut &str) -> Vec<u16> {
+    let max_args = command::find(name).map_or(usize::MAX, |command| command.num_args);
+    let mut args = vec![];
+    while args.len() < max_args {
+        let candidate = if args.is_empty() {
+            rest.get(0..4)
+        } else if rest.starts_with(':') {
+            rest.get(1..5)
+        } else {
+            None
+        };
+        match candidate.and_then(parse_number) {
+            Some(arg) => {
+                let consumed = if args.is_empty() { 4 } else { 5 };
+                *rest = &rest[consumed..];
+                args.push(arg);
+            }
+            None => break,
+        }
+    }
+    args
+}
+
+// The original engine reads each of the four characters as a digit without checking it, so
+// only plain digits are accepted here.
+fn parse_number(text: &str) -> Option<u16> {
+    if text.len() == 4 && text.chars().all(|ch| ch.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(script: &Script, number: u16) -> Vec<Token> {
+        script
+            .event(number)
+            .unwrap()
+            .tokens()
+            .iter()
+            .map(|(token, _)| token.clone())
+            .collect()
+    }
+
+    #[test]
+    fn commands_can_end_a_line() {
+        let script = Script::parse("#0001\nHi<NOD\n<WAI0010\nBye<EN").unwrap();
+        assert_eq!(tokens(&script, 1),
+                   [Token::Text(String::from("Hi")),
+                    Token::Command(String::from("NOD"), vec![]),
+                    Token::NewLine,
+                    Token::Command(String::from("WAI"), vec![10]),
+                    Token::NewLine,
+                    Token::Text(String::from("Bye<EN")),
+                    Token::NewLine]);
+    }
+
+    #[test]
+    fn non_ascii_after_a_bracket_is_text() {
+        let script = Script::parse("#0001\n<éé").unwrap();
+        assert_eq!(tokens(&script, 1),
+                   [Token::Text(String::from("<éé")), Token::NewLine]);
+    }
+
+    #[test]
+    fn the_first_duplicate_event_wins() {
+        let script = Script::parse("#0001\n<END\n#0001\n<NOD").unwrap();
+        assert_eq!(script.events().len(), 2);
+        assert_eq!(tokens(&script, 1),
+                   [Token::Command(String::from("END"), vec![]), Token::NewLine]);
+    }
+}
@@ -25,6 +25,7 @@ impl Sub<Millis> for Millis {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
 pub struct Velocity(pub f64);
 
 impl Neg for Velocity {
@@ -0,0 +1,187 @@
+pub const MAX_LEVEL: u8 = 3;
+
+struct WeaponInfo {
+    name: &'static str,
+    // Experience needed to fill each level's bar
+    exp_per_level: [u16; 3],
+}
+
+// Weapons by the number scripts use, in the original game's order
+const WEAPONS: &[WeaponInfo] = &[WeaponInfo {
+                                     name: "",
+                                     exp_per_level: [0, 0, 0],
+                                 },
+                                 WeaponInfo {
+                                     name: "Snake",
+                                     exp_per_level: [30, 40, 16],
+                                 },
+                                 WeaponInfo {
+                                     name: "Polar Star",
+                                     exp_per_level: [10, 20, 10],
+                                 },
+                                 WeaponInfo {
+                                     name: "Fireball",
+                                     exp_per_level: [10, 20, 20],
+                                 },
+                                 WeaponInfo {
+                                     name: "Machine Gun",
+                                     exp_per_level: [30, 40, 10],
+                                 },
+                                 WeaponInfo {
+                                     name: "Missile Launcher",
+                                     exp_per_level: [10, 20, 10],
+                                 },
+                                 WeaponInfo {
+                                     name: "",
+                                     exp_per_level: [10, 20, 30],
+                                 },
+                                 WeaponInfo {
+                                     name: "Bubbler",
+                                     exp_per_level: [10, 20, 5],
+                                 },
+                                 WeaponInfo {
+                                     name: "",
+                                     exp_per_level: [10, 20, 100],
+                                 },
+                                 WeaponInfo {
+                                     name: "Blade",
+                                     exp_per_level: [30, 60, 0],
+                                 },
+                                 WeaponInfo {
+                                     name: "Super Missile Launcher",
+                                     exp_per_level: [30, 60, 10],
+                                 },
+                                 WeaponInfo {
+                                     name: "",
+                                     exp_per_level: [10, 20, 100],
+                                 },
+                                 WeaponInfo {
+                                     name: "Nemesis",
+                                     exp_per_level: [1, 1, 1],
+                                 },
+                                 WeaponInfo {
+                                     name: "Spur",
+                                     exp_per_level: [40, 60, 200],
+                                 }];
+
+pub fn is_known_weapon(id: u16) -> bool {
+    id != 0 && (id as usize) < WEAPONS.len()
+}
+
+pub fn weapon_name(id: u16) -> &'static str {
+    WEAPONS.get(id as usize).map_or("", |info| info.name)
+}
+
+// A weapon the player is carrying. Experience fills up each level in turn and drains back
+// down, losing levels, when the player is hurt.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Weapon {
+    id: u16,
+    level: u8,
+    exp: u16,
+    // Weapons with a max of 0 never run out
+    ammo: u16,
+    max_ammo: u16,
+}
+
+impl Weapon {
+    pub fn new(id: u16, max_ammo: u16) -> Weapon {
+        Weapon {
+            id,
+            level: 1,
+            exp: 0,
+            ammo: max_ammo,
+            max_ammo,
+        }
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn name(&self) -> &'static str {
+        weapon_name(self.id)
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn exp(&self) -> u16 {
+        self.exp
+    }
+
+    // Experience that fills the current level's bar
+    pub fn exp_for_level(&self) -> u16 {
+        WEAPONS
+            .get(self.id as usize)
+            .map_or(0, |info| info.exp_per_level[self.level as usize - 1])
+    }
+
+    pub fn is_max_level(&self) -> bool {
+        self.level == MAX_LEVEL && self.exp >= self.exp_for_level()
+    }
+
+    pub fn add_exp(&mut self, amount: u16) {
+        self.exp = self.exp.saturating_add(amount);
+        while self.exp >= self.exp_for_level() && self.level < MAX_LEVEL {
+            self.exp -= self.exp_for_level();
+            self.level += 1;
+        }
+        if self.level == MAX_LEVEL {
+            self.exp = self.exp.min(self.exp_for_level());
+        }
+    }
+
+    pub fn lose_exp(&mut self, amount: u16) {
+        let mut exp = self.exp as i32 - amount as i32;
+        while exp < 0 && self.level > 1 {
+            self.level -= 1;
+            exp += self.exp_for_level() as i32;
+        }
+        self.exp = exp.max(0) as u16;
+    }
+
+    pub fn set_level(&mut self, level: u8, exp: u16) {
+        self.level = level.max(1).min(MAX_LEVEL);
+        self.exp = exp.min(self.exp_for_level());
+    }
+
+    pub fn has_ammo_limit(&self) -> bool {
+        self.max_ammo != 0
+    }
+
+    pub fn ammo(&self) -> u16 {
+        self.ammo
+    }
+
+    pub fn max_ammo(&self) -> u16 {
+        self.max_ammo
+    }
+
+    // Returns false without firing when there isn't enough left
+    pub fn use_ammo(&mut self, amount: u16) -> bool {
+        if !self.has_ammo_limit() {
+            return true;
+        }
+        if self.ammo < amount {
+            return false;
+        }
+        self.ammo -= amount;
+        true
+    }
+
+    pub fn add_max_ammo(&mut self, amount: u16) {
+        self.max_ammo = self.max_ammo.saturating_add(amount);
+        self.ammo = self.ammo.saturating_add(amount);
+    }
+
+    pub fn set_ammo(&mut self, ammo: u16, max_ammo: u16) {
+        self.max_ammo = max_ammo;
+        self.ammo = ammo.min(max_ammo);
+    }
+
+    pub fn refill_ammo(&mut self) {
+        self.ammo = self.max_ammo;
+    }
+}